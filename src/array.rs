@@ -7,10 +7,33 @@ pub enum Dim {
     Jagged,
 }
 pub struct Shape(Vec<Dim>);
+
+impl Shape {
+    // Estimates the number of bytes `element_size`-wide elements of this
+    // shape would take up. `Dim::Var` and `Dim::Jagged` dimensions aren't
+    // known ahead of time, so each contributes a lower bound of 1 and
+    // marks the result dynamic.
+    pub fn estimate_size(&self, element_size: usize) -> crate::graph::SizeHint {
+        let mut count = 1usize;
+        let mut is_dynamic = false;
+        for dim in &self.0 {
+            match dim {
+                Dim::Fixed(n) => count *= n,
+                Dim::Var(_) | Dim::Jagged => is_dynamic = true,
+            }
+        }
+        crate::graph::SizeHint {
+            bytes: count * element_size,
+            is_dynamic,
+        }
+    }
+}
+
 pub enum ArrayImpl {}
 
 // The array type actually owns the
 // memory of an array
+#[derive(Debug, Clone)]
 pub struct Array<'ctx>(PhantomData<&'ctx ()>);
 
 impl<'a, 'ctx> Borrow<ArrayView<'a, 'ctx>> for Array<'ctx> {
@@ -19,7 +42,12 @@ impl<'a, 'ctx> Borrow<ArrayView<'a, 'ctx>> for Array<'ctx> {
     }
 }
 
-// A cheap-to-clone view into an array.
+// A view into an array. Note this itself is NOT `Clone`:
+// it's the `ToOwned` target (`Array`) of a `Cow` that needs to be
+// cloneable, and a manual `ToOwned` impl (`Owned = Array`, below)
+// conflicts with the standard library's blanket `impl<T: Clone> ToOwned
+// for T` if this type were also `Clone`.
+#[derive(Debug, PartialEq)]
 pub struct ArrayView<'a, 'ctx>(PhantomData<&'a Array<'ctx>>);
 
 impl<'a, 'ctx> ToOwned for ArrayView<'a, 'ctx> {
@@ -1,6 +1,7 @@
 use pyo3::prelude::*;
 
 mod utils;
+mod value;
 
 #[pymodule]
 #[pyo3(name = "picoml")]
@@ -10,8 +11,26 @@ pub mod root {
     #[pyo3(name = "_lib")]
     pub mod lib {
         use super::super::utils::*;
+        use super::super::value::{py_to_value, value_to_py};
+        use crate::format::bin::{BinDeserializer, BinSerializer};
+        use crate::format::ron::{RonDeserializer, RonSerializer};
+        use crate::graph::{
+            GraphDeserializer, GraphSerializer, NoModel, TranscodeError, Value, ValueVisitor,
+        };
+        use pyo3::exceptions::PyNotImplementedError;
         use pyo3::prelude::*;
+        use pyo3::types::{PyBytes, PyString};
+        use std::borrow::Borrow;
+        use std::io::{BufReader, BufWriter, Write};
 
+        // RonFormat and BinFormat are both thin PyO3 wrappers: the actual
+        // encoding/decoding is driven through the graph::GraphSerializer /
+        // graph::GraphDeserializer traits, so a document written with
+        // either format can be read back losslessly through the other --
+        // `convert` below relies on exactly that. One exception: RON
+        // writes struct names and field names bare, so it reads a struct
+        // back only when those are plain identifiers (a struct name starts
+        // with a letter; both use only letters, digits and `_`).
         #[pyclass]
         struct RonFormat;
 
@@ -21,15 +40,240 @@ pub mod root {
             fn new() -> Self {
                 RonFormat {}
             }
-            fn serialize<'py>(&self, py: Python<'py>, obj: PyObject) -> PyDataBuffer {
-                todo!()
+
+            fn serialize(&self, py: Python<'_>, obj: PyObject) -> PyResult<PyDataBuffer> {
+                let value = py_to_value(obj.bind(py))?;
+                let mut ser = RonSerializer::with_capacity_for(&value);
+                ser.serialize(&value)?;
+                let text = String::from_utf8(ser.into_inner())
+                    .expect("RonEncodeVisitor only ever emits valid UTF-8");
+                Ok(PyDataBuffer::String(PyString::new(py, &text).unbind()))
+            }
+
+            fn serialize_to(&self, py: Python<'_>, obj: PyObject, file: PyIO) -> PyResult<()> {
+                let value = py_to_value(obj.bind(py))?;
+                match &file {
+                    // Nothing below touches a Python object, so the whole
+                    // encode can run with the GIL released.
+                    PyIO::NativeFile(native) => {
+                        let f: &std::fs::File = native.borrow();
+                        py.allow_threads(|| {
+                            let mut writer = BufWriter::new(f);
+                            let mut ser = RonSerializer::new(&mut writer);
+                            ser.serialize(&value)?;
+                            writer.flush()
+                        })?;
+                        Ok(())
+                    }
+                    // `PyWriter::flush` calls back into Python, so the GIL
+                    // has to stay held for the whole write.
+                    PyIO::PythonFile(_) => {
+                        let mut writer = PyWriter::new(py, &file);
+                        let mut ser = RonSerializer::new(&mut writer);
+                        ser.serialize(&value)?;
+                        writer.flush()?;
+                        Ok(())
+                    }
+                }
+            }
+
+            fn deserialize(&self, py: Python<'_>, file: PyIO) -> PyResult<PyObject> {
+                let value: Value<'static, 'static, String> = match &file {
+                    PyIO::NativeFile(native) => {
+                        let f: &std::fs::File = native.borrow();
+                        py.allow_threads(|| {
+                            let mut reader = BufReader::new(f);
+                            let mut de = RonDeserializer::new(&mut reader);
+                            de.deserialize(&NoModel, ValueVisitor::new())
+                        })?
+                    }
+                    PyIO::PythonFile(_) => {
+                        let mut reader = PyReader::new(py, &file);
+                        let mut de = RonDeserializer::new(&mut reader);
+                        de.deserialize(&NoModel, ValueVisitor::new())?
+                    }
+                };
+                value_to_py(py, &value)
+            }
+        }
+
+        // Compact, self-describing binary transfer syntax. Same contract
+        // as RonFormat, just a different concrete GraphSerializer /
+        // GraphDeserializer pair underneath -- `format::bin::BinSerializer`
+        // / `BinDeserializer`, driven through the dynamic `Value` IR so any
+        // acyclic Python object made of bool/int (within i64)/float/str/
+        // list/tuple/dict (with str keys) can be round-tripped without a
+        // bespoke schema.
+        #[pyclass]
+        struct BinFormat;
+
+        #[pymethods]
+        impl BinFormat {
+            #[new]
+            fn new() -> Self {
+                BinFormat {}
+            }
+
+            fn serialize(&self, py: Python<'_>, obj: PyObject) -> PyResult<PyDataBuffer> {
+                let value = py_to_value(obj.bind(py))?;
+                let mut ser = BinSerializer::with_capacity_for(&value);
+                ser.serialize(&value)?;
+                Ok(PyDataBuffer::Bytes(
+                    PyBytes::new(py, &ser.into_inner()).unbind(),
+                ))
+            }
+
+            fn serialize_to(&self, py: Python<'_>, obj: PyObject, file: PyIO) -> PyResult<()> {
+                let value = py_to_value(obj.bind(py))?;
+                match &file {
+                    // Nothing below touches a Python object, so the whole
+                    // encode can run with the GIL released.
+                    PyIO::NativeFile(native) => {
+                        let f: &std::fs::File = native.borrow();
+                        py.allow_threads(|| {
+                            let mut writer = BufWriter::new(f);
+                            let mut ser = BinSerializer::new(&mut writer);
+                            ser.serialize(&value)?;
+                            writer.flush()
+                        })?;
+                        Ok(())
+                    }
+                    // `PyWriter::flush` calls back into Python, so the GIL
+                    // has to stay held for the whole write.
+                    PyIO::PythonFile(_) => {
+                        let mut writer = PyWriter::new(py, &file);
+                        let mut ser = BinSerializer::new(&mut writer);
+                        ser.serialize(&value)?;
+                        writer.flush()?;
+                        Ok(())
+                    }
+                }
+            }
+
+            fn deserialize(&self, py: Python<'_>, file: PyIO) -> PyResult<PyObject> {
+                let value: Value<'static, 'static, String> = match &file {
+                    PyIO::NativeFile(native) => {
+                        let f: &std::fs::File = native.borrow();
+                        py.allow_threads(|| {
+                            let mut reader = BufReader::new(f);
+                            let mut de = BinDeserializer::new(&mut reader);
+                            de.deserialize(&NoModel, ValueVisitor::new())
+                        })?
+                    }
+                    PyIO::PythonFile(_) => {
+                        let mut reader = PyReader::new(py, &file);
+                        let mut de = BinDeserializer::new(&mut reader);
+                        de.deserialize(&NoModel, ValueVisitor::new())?
+                    }
+                };
+                value_to_py(py, &value)
+            }
+        }
+
+        fn transcode_err_to_py(e: TranscodeError<std::io::Error, std::io::Error>) -> PyErr {
+            match e {
+                TranscodeError::Deserialize(e) => PyErr::from(e),
+                TranscodeError::Serialize(e) => PyErr::from(e),
             }
-            fn serialize_to<'py>(&self, py: Python<'py>, obj: PyObject, file: PyIO) {
-                todo!()
+        }
+
+        #[derive(Clone, Copy)]
+        enum Codec {
+            Bin,
+            Ron,
+        }
+
+        // Transcodes `reader` (in `src_format`) to `writer` (in
+        // `dst_format`) without ever materializing a Python object in
+        // between, so round-tripping through either transfer syntax is
+        // lossless (up to the RON struct-name caveat above) -- all four `BinFormat`/`RonFormat` combinations go
+        // through the same dynamic `Value` IR via `graph::transcode`.
+        #[pyfunction]
+        fn convert(
+            py: Python<'_>,
+            src_format: PyObject,
+            dst_format: PyObject,
+            reader: PyIO,
+            writer: PyIO,
+        ) -> PyResult<()> {
+            let src = src_format.bind(py);
+            let dst = dst_format.bind(py);
+            let src_codec = if src.is_instance_of::<BinFormat>() {
+                Some(Codec::Bin)
+            } else if src.is_instance_of::<RonFormat>() {
+                Some(Codec::Ron)
+            } else {
+                None
+            };
+            let dst_codec = if dst.is_instance_of::<BinFormat>() {
+                Some(Codec::Bin)
+            } else if dst.is_instance_of::<RonFormat>() {
+                Some(Codec::Ron)
+            } else {
+                None
+            };
+            let (src_codec, dst_codec) = match (src_codec, dst_codec) {
+                (Some(s), Some(d)) => (s, d),
+                _ => {
+                    return Err(PyNotImplementedError::new_err(
+                        "convert() only supports BinFormat/RonFormat src/dst combinations",
+                    ))
+                }
+            };
+
+            // When both ends are real OS files, nothing below touches a
+            // Python object, so the whole transcode can run with the GIL
+            // released -- same rationale as `BinFormat::serialize_to`.
+            if let (PyIO::NativeFile(rf), PyIO::NativeFile(wf)) = (&reader, &writer) {
+                let rf: &std::fs::File = rf.borrow();
+                let wf: &std::fs::File = wf.borrow();
+                return py
+                    .allow_threads(|| {
+                        let mut r = BufReader::new(rf);
+                        let mut w = BufWriter::new(wf);
+                        match (src_codec, dst_codec) {
+                            (Codec::Bin, Codec::Bin) => crate::graph::transcode::<String, _, _>(
+                                &mut BinDeserializer::new(&mut r),
+                                &mut BinSerializer::new(&mut w),
+                            ),
+                            (Codec::Bin, Codec::Ron) => crate::graph::transcode::<String, _, _>(
+                                &mut BinDeserializer::new(&mut r),
+                                &mut RonSerializer::new(&mut w),
+                            ),
+                            (Codec::Ron, Codec::Bin) => crate::graph::transcode::<String, _, _>(
+                                &mut RonDeserializer::new(&mut r),
+                                &mut BinSerializer::new(&mut w),
+                            ),
+                            (Codec::Ron, Codec::Ron) => crate::graph::transcode::<String, _, _>(
+                                &mut RonDeserializer::new(&mut r),
+                                &mut RonSerializer::new(&mut w),
+                            ),
+                        }
+                    })
+                    .map_err(transcode_err_to_py);
             }
-            fn deserialize<'py>(&self, py: Python<'py>) {
-                todo!()
+
+            let mut r = PyReader::new(py, &reader);
+            let mut w = PyWriter::new(py, &writer);
+            match (src_codec, dst_codec) {
+                (Codec::Bin, Codec::Bin) => crate::graph::transcode::<String, _, _>(
+                    &mut BinDeserializer::new(&mut r),
+                    &mut BinSerializer::new(&mut w),
+                ),
+                (Codec::Bin, Codec::Ron) => crate::graph::transcode::<String, _, _>(
+                    &mut BinDeserializer::new(&mut r),
+                    &mut RonSerializer::new(&mut w),
+                ),
+                (Codec::Ron, Codec::Bin) => crate::graph::transcode::<String, _, _>(
+                    &mut RonDeserializer::new(&mut r),
+                    &mut BinSerializer::new(&mut w),
+                ),
+                (Codec::Ron, Codec::Ron) => crate::graph::transcode::<String, _, _>(
+                    &mut RonDeserializer::new(&mut r),
+                    &mut RonSerializer::new(&mut w),
+                ),
             }
+            .map_err(transcode_err_to_py)
         }
     }
 }
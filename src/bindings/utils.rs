@@ -7,6 +7,7 @@ use pyo3::{FromPyObject, PyObject};
 
 use std::borrow::Borrow;
 use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
 use std::mem::ManuallyDrop;
 use std::os::fd::{FromRawFd, RawFd};
 
@@ -68,6 +69,148 @@ pub enum PyDataBuffer {
     String(Py<PyString>),
 }
 
+fn to_io_err(e: PyErr) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+// Adapts a `PyIO` to `std::io::Write` so a serializer can drive it with
+// the standard `Write` combinators (e.g. wrap it in a `BufWriter`)
+// regardless of whether the file is a native fd or a pure-Python object.
+// `NativeFile` writes straight to the OS file, never round-tripping bytes
+// through the interpreter; `PythonFile` batches writes into memory and
+// only touches the GIL on `flush`.
+pub enum PyWriter<'py> {
+    NativeFile(BufWriter<&'py File>),
+    PythonFile {
+        py: Python<'py>,
+        file: &'py PyObject,
+        is_text: bool,
+        buf: Vec<u8>,
+    },
+}
+
+impl<'py> PyWriter<'py> {
+    pub fn new(py: Python<'py>, io: &'py PyIO) -> Self {
+        match io {
+            PyIO::NativeFile(f) => PyWriter::NativeFile(BufWriter::new(f.borrow())),
+            PyIO::PythonFile(f) => PyWriter::PythonFile {
+                py,
+                file: &f.inner,
+                is_text: f.is_text,
+                buf: Vec::new(),
+            },
+        }
+    }
+}
+
+impl<'py> Write for PyWriter<'py> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        match self {
+            PyWriter::NativeFile(w) => w.write(data),
+            PyWriter::PythonFile { buf, .. } => {
+                buf.extend_from_slice(data);
+                Ok(data.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            PyWriter::NativeFile(w) => w.flush(),
+            PyWriter::PythonFile {
+                py,
+                file,
+                is_text,
+                buf,
+            } => {
+                if *is_text {
+                    let text = std::str::from_utf8(buf).map_err(|e| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("cannot write non-UTF-8 data to a text-mode file: {e}"),
+                        )
+                    })?;
+                    file.bind(*py)
+                        .call_method1(consts::write(*py), (text,))
+                        .map_err(to_io_err)?;
+                } else {
+                    let bytes = PyBytes::new(*py, buf);
+                    file.bind(*py)
+                        .call_method1(consts::write(*py), (bytes,))
+                        .map_err(to_io_err)?;
+                }
+                file.bind(*py)
+                    .call_method0(consts::flush(*py))
+                    .map_err(to_io_err)?;
+                buf.clear();
+                Ok(())
+            }
+        }
+    }
+}
+
+// The read-side counterpart of `PyWriter`.
+pub enum PyReader<'py> {
+    NativeFile(BufReader<&'py File>),
+    PythonFile {
+        py: Python<'py>,
+        file: &'py PyObject,
+        is_text: bool,
+        // Bytes already decoded from Python but not yet handed to the
+        // caller. In text mode, `file.read(n)` is asked for `n`
+        // characters but returns however many UTF-8 bytes those encode
+        // to, which can be more than `buf.len()` -- the remainder is
+        // stashed here instead of being dropped, and served before the
+        // next call back into Python.
+        pending: Vec<u8>,
+    },
+}
+
+impl<'py> PyReader<'py> {
+    pub fn new(py: Python<'py>, io: &'py PyIO) -> Self {
+        match io {
+            PyIO::NativeFile(f) => PyReader::NativeFile(BufReader::new(f.borrow())),
+            PyIO::PythonFile(f) => PyReader::PythonFile {
+                py,
+                file: &f.inner,
+                is_text: f.is_text,
+                pending: Vec::new(),
+            },
+        }
+    }
+}
+
+impl<'py> Read for PyReader<'py> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            PyReader::NativeFile(r) => r.read(buf),
+            PyReader::PythonFile {
+                py,
+                file,
+                is_text,
+                pending,
+            } => {
+                if pending.is_empty() {
+                    let chunk = file
+                        .bind(*py)
+                        .call_method1(consts::read(*py), (buf.len(),))
+                        .map_err(to_io_err)?;
+                    *pending = if *is_text {
+                        let text: String = chunk.extract().map_err(to_io_err)?;
+                        text.into_bytes()
+                    } else {
+                        chunk.extract().map_err(to_io_err)?
+                    };
+                }
+                let n = pending.len().min(buf.len());
+                buf[..n].copy_from_slice(&pending[..n]);
+                pending.drain(..n);
+                Ok(n)
+            }
+        }
+    }
+}
+
 mod consts {
     use pyo3::prelude::*;
     use pyo3::sync::GILOnceCell;
@@ -0,0 +1,246 @@
+// A minimal bridge between Python objects and `graph::Value`, so
+// `RonFormat`/`BinFormat` have something real to serialize instead of an
+// opaque `PyObject`. Deliberately narrow: only the handful of types a
+// config format actually needs to round-trip are supported.
+
+use crate::graph::Value;
+use crate::types::Primitive;
+use pyo3::exceptions::{PyNotImplementedError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use std::collections::{HashMap, HashSet};
+
+// `list`/`tuple`/`dict` are the only Python types that can participate in
+// sharing or cycles (a `bool`/`int`/`float`/`str` is always copied by
+// value), so only these need their identity tracked. `as_ptr()` is stable
+// for the lifetime of the object and unique among live objects, which is
+// all the two passes below need -- it's never dereferenced as a pointer.
+fn container_ptr(obj: &Bound<'_, PyAny>) -> Option<usize> {
+    if obj.downcast::<PyList>().is_ok()
+        || obj.downcast::<PyTuple>().is_ok()
+        || obj.downcast::<PyDict>().is_ok()
+    {
+        Some(obj.as_ptr() as usize)
+    } else {
+        None
+    }
+}
+
+// First pass: counts how many times each container is reachable from
+// `obj`, by identity. `in_progress` stops the walk from recursing forever
+// on a cycle -- the count for a self-referential container is still
+// correctly bumped to >1 (since that happens before the `in_progress`
+// check), it just isn't descended into a second time. `finished` is a
+// separate set from `in_progress`: once a container's subtree has been
+// fully walked once, a later sibling reference to it only needs its count
+// bumped, not a full re-walk -- without this, a DAG built by repeated
+// sharing (e.g. `a = [1]; for _ in range(30): a = [a, a]`) would redo the
+// whole subtree at every re-encounter, blowing up exponentially in the
+// depth of the sharing.
+fn count_container_refs(
+    obj: &Bound<'_, PyAny>,
+    in_progress: &mut HashSet<usize>,
+    finished: &mut HashSet<usize>,
+    counts: &mut HashMap<usize, usize>,
+) {
+    let Some(ptr) = container_ptr(obj) else {
+        return;
+    };
+    *counts.entry(ptr).or_insert(0) += 1;
+    if finished.contains(&ptr) {
+        return;
+    }
+    if !in_progress.insert(ptr) {
+        return;
+    }
+    if let Ok(list) = obj.downcast::<PyList>() {
+        for item in list.iter() {
+            count_container_refs(&item, in_progress, finished, counts);
+        }
+    } else if let Ok(tuple) = obj.downcast::<PyTuple>() {
+        for item in tuple.iter() {
+            count_container_refs(&item, in_progress, finished, counts);
+        }
+    } else if let Ok(dict) = obj.downcast::<PyDict>() {
+        for (_, v) in dict.iter() {
+            count_container_refs(&v, in_progress, finished, counts);
+        }
+    }
+    in_progress.remove(&ptr);
+    finished.insert(ptr);
+}
+
+// Second pass: builds the actual `Value` tree, consulting the counts from
+// the first pass to decide which containers need a `Value::Shared` index.
+// `assigned` only gets an entry for a container once its whole subtree has
+// finished building (i.e. after recursion returns, not before) -- that's
+// what lets `in_progress` double as cycle detection: a reference to a
+// container still on the active recursion stack is never in `assigned`,
+// so it can only be a genuine cycle, not a finished shared subgraph.
+struct ValueBuilder {
+    counts: HashMap<usize, usize>,
+    assigned: HashMap<usize, usize>,
+    in_progress: HashSet<usize>,
+    next_idx: usize,
+}
+
+impl ValueBuilder {
+    fn build(&mut self, obj: &Bound<'_, PyAny>) -> PyResult<Value<'static, 'static, String>> {
+        let Some(ptr) = container_ptr(obj) else {
+            return build_scalar(obj);
+        };
+        if let Some(&idx) = self.assigned.get(&ptr) {
+            return Ok(Value::Ref(idx));
+        }
+        if !self.in_progress.insert(ptr) {
+            return Err(PyValueError::new_err(
+                "cannot serialize a self-referential (cyclic) object graph",
+            ));
+        }
+        let built = self.build_container(obj)?;
+        self.in_progress.remove(&ptr);
+
+        if self.counts.get(&ptr).copied().unwrap_or(1) > 1 {
+            let idx = self.next_idx;
+            self.next_idx += 1;
+            self.assigned.insert(ptr, idx);
+            Ok(Value::Shared(idx, Box::new(built)))
+        } else {
+            Ok(built)
+        }
+    }
+
+    fn build_container(&mut self, obj: &Bound<'_, PyAny>) -> PyResult<Value<'static, 'static, String>> {
+        if let Ok(list) = obj.downcast::<PyList>() {
+            let items = list
+                .iter()
+                .map(|item| self.build(&item))
+                .collect::<PyResult<Vec<_>>>()?;
+            return Ok(Value::List(items));
+        }
+        if let Ok(tuple) = obj.downcast::<PyTuple>() {
+            let items = tuple
+                .iter()
+                .map(|item| self.build(&item))
+                .collect::<PyResult<Vec<_>>>()?;
+            return Ok(Value::Tuple(items));
+        }
+        let dict = obj.downcast::<PyDict>().expect("container_ptr guarantees this");
+        let mut entries = Vec::with_capacity(dict.len());
+        for (k, v) in dict.iter() {
+            let key: String = k.extract()?;
+            entries.push((key, self.build(&v)?));
+        }
+        Ok(Value::Map(entries))
+    }
+}
+
+fn build_scalar(obj: &Bound<'_, PyAny>) -> PyResult<Value<'static, 'static, String>> {
+    // `bool` must be checked before `int`: in Python, `bool` is a
+    // subclass of `int`, so `downcast::<PyInt>` would otherwise also
+    // accept `True`/`False`.
+    if let Ok(b) = obj.downcast::<PyBool>() {
+        return Ok(Value::Primitive(Primitive::Bool(b.is_true())));
+    }
+    if let Ok(i) = obj.downcast::<PyInt>() {
+        return Ok(Value::Primitive(Primitive::I64(i.extract()?)));
+    }
+    if let Ok(f) = obj.downcast::<PyFloat>() {
+        return Ok(Value::Primitive(Primitive::F64(f.extract()?)));
+    }
+    if let Ok(s) = obj.downcast::<PyString>() {
+        return Ok(Value::Leaf(s.extract()?));
+    }
+    Err(PyTypeError::new_err(format!(
+        "unsupported value of type {}: nanoconfig only knows how to serialize \
+         bool, int, float, str, list, tuple, and dict",
+        obj.get_type().name()?
+    )))
+}
+
+pub fn py_to_value(obj: &Bound<'_, PyAny>) -> PyResult<Value<'static, 'static, String>> {
+    let mut counts = HashMap::new();
+    count_container_refs(obj, &mut HashSet::new(), &mut HashSet::new(), &mut counts);
+    let mut builder = ValueBuilder {
+        counts,
+        assigned: HashMap::new(),
+        in_progress: HashSet::new(),
+        next_idx: 0,
+    };
+    builder.build(obj)
+}
+
+pub fn value_to_py(py: Python<'_>, value: &Value<'_, '_, String>) -> PyResult<PyObject> {
+    let mut resolver = HashMap::new();
+    value_to_py_inner(py, value, &mut resolver)
+}
+
+// Mirrors `ValueBuilder::build`: a `Value::Shared(idx, inner)` registers
+// its built object under `idx` (cloning the reference, not the object --
+// `clone_ref` is a refcount bump) before returning it, and a later
+// `Value::Ref(idx)` looks the same object back up. This relies on
+// `Shared(idx, ..)` always appearing before any `Ref(idx)` in a
+// depth-first walk of `value`, which holds because `py_to_value` only
+// assigns an index after a subtree finishes building, and every format in
+// this crate preserves child order through encode/decode.
+fn value_to_py_inner(
+    py: Python<'_>,
+    value: &Value<'_, '_, String>,
+    resolver: &mut HashMap<usize, PyObject>,
+) -> PyResult<PyObject> {
+    match value {
+        Value::Primitive(Primitive::Bool(v)) => Ok(v.into_pyobject(py)?.to_owned().into_any().unbind()),
+        Value::Primitive(Primitive::I8(v)) => Ok(v.into_pyobject(py)?.into_any().unbind()),
+        Value::Primitive(Primitive::I16(v)) => Ok(v.into_pyobject(py)?.into_any().unbind()),
+        Value::Primitive(Primitive::I32(v)) => Ok(v.into_pyobject(py)?.into_any().unbind()),
+        Value::Primitive(Primitive::I64(v)) => Ok(v.into_pyobject(py)?.into_any().unbind()),
+        Value::Primitive(Primitive::U8(v)) => Ok(v.into_pyobject(py)?.into_any().unbind()),
+        Value::Primitive(Primitive::U16(v)) => Ok(v.into_pyobject(py)?.into_any().unbind()),
+        Value::Primitive(Primitive::U32(v)) => Ok(v.into_pyobject(py)?.into_any().unbind()),
+        Value::Primitive(Primitive::U64(v)) => Ok(v.into_pyobject(py)?.into_any().unbind()),
+        Value::Primitive(Primitive::F32(v)) => Ok(v.into_pyobject(py)?.into_any().unbind()),
+        Value::Primitive(Primitive::F64(v)) => Ok(v.into_pyobject(py)?.into_any().unbind()),
+        Value::Leaf(s) => Ok(s.into_pyobject(py)?.into_any().unbind()),
+        Value::List(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(value_to_py_inner(py, item, resolver)?)?;
+            }
+            Ok(list.into_any().unbind())
+        }
+        Value::Tuple(items) => {
+            let elems = items
+                .iter()
+                .map(|item| value_to_py_inner(py, item, resolver))
+                .collect::<PyResult<Vec<_>>>()?;
+            Ok(PyTuple::new(py, elems)?.into_any().unbind())
+        }
+        Value::Map(entries) => {
+            let dict = PyDict::new(py);
+            for (k, v) in entries {
+                dict.set_item(k, value_to_py_inner(py, v, resolver)?)?;
+            }
+            Ok(dict.into_any().unbind())
+        }
+        Value::Struct { fields, .. } => {
+            let dict = PyDict::new(py);
+            for (k, v) in fields {
+                dict.set_item(k, value_to_py_inner(py, v, resolver)?)?;
+            }
+            Ok(dict.into_any().unbind())
+        }
+        Value::Array(_) => Err(PyNotImplementedError::new_err(
+            "array values are not yet supported (array::ArrayView has no backing storage upstream)",
+        )),
+        Value::Shared(idx, inner) => {
+            let obj = value_to_py_inner(py, inner, resolver)?;
+            resolver.insert(*idx, obj.clone_ref(py));
+            Ok(obj)
+        }
+        Value::Ref(idx) => resolver.get(idx).map(|obj| obj.clone_ref(py)).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "dangling reference to index {idx}: no earlier shared value was seen"
+            ))
+        }),
+    }
+}
@@ -0,0 +1,555 @@
+// A compact, self-describing binary transfer syntax: each value is a tag
+// byte followed by its payload, with lengths and repeat counts written
+// as unsigned LEB128 varints. This is the concrete `GraphSerializer`/
+// `GraphDeserializer` pair `BinFormat` is meant to drive (see
+// `src/bindings/mod.rs`).
+
+use crate::graph::{GraphDeserializer, GraphSerialize, GraphSerializer, Visitor};
+use crate::types::{Primitive, TypeInfo};
+use std::io::{self, Read, Write};
+
+const TAG_BOOL: u8 = 0;
+const TAG_I8: u8 = 1;
+const TAG_I16: u8 = 2;
+const TAG_I32: u8 = 3;
+const TAG_I64: u8 = 4;
+const TAG_U8: u8 = 5;
+const TAG_U16: u8 = 6;
+const TAG_U32: u8 = 7;
+const TAG_U64: u8 = 8;
+const TAG_F32: u8 = 9;
+const TAG_F64: u8 = 10;
+const TAG_LEAF: u8 = 11;
+const TAG_SEQ: u8 = 12;
+const TAG_TUPLE: u8 = 13;
+const TAG_MAP: u8 = 14;
+const TAG_STRUCT: u8 = 15;
+const TAG_ARRAY: u8 = 16;
+// Also the wire tag `GraphSerializer::serialize_index`/
+// `GraphDeserializer::deserialize_index` use for the separate `Ref<T>`/
+// `RefTable` model-object mechanism (see the `serialize_ref`/
+// `deserialize_ref` default methods and the `SharedValue` test below).
+// That mechanism reads/writes this tag directly rather than going through
+// `decode`'s tag dispatch, and `serialize_ref` follows the marker with a
+// full `serialize` of the object in the same byte stream. Both uses agree
+// that "TAG_REF + a uvarint index" means "indirect reference", so the
+// marker reads the same whichever side decodes it.
+const TAG_REF: u8 = 17;
+const TAG_SHARED: u8 = 18;
+
+fn write_uvarint(w: &mut impl Write, mut n: u64) -> io::Result<()> {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            w.write_all(&[byte])?;
+            return Ok(());
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_uvarint(r: &mut impl Read) -> io::Result<u64> {
+    let mut result: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        r.read_exact(&mut byte)?;
+        result |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(result);
+        }
+        shift += 7;
+    }
+}
+
+fn write_bytes_with_len(w: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    write_uvarint(w, bytes.len() as u64)?;
+    w.write_all(bytes)
+}
+
+// Reads exactly `len` bytes, where `len` comes from the wire and so must
+// never be trusted as an allocation size up front: a `vec![0u8; len]`
+// ahead of `read_exact` would let a few bytes of crafted input (a bogus
+// multi-exabyte length) trigger an out-of-memory abort before the
+// mismatch is ever detected. `Take::read_to_end` instead grows the buffer
+// incrementally as bytes actually arrive, so a truncated source just
+// yields a short buffer, which is then checked against `len`.
+fn read_bytes_with_len(r: &mut impl Read) -> io::Result<Vec<u8>> {
+    let len = read_uvarint(r)?;
+    let mut buf = Vec::new();
+    let read = r.take(len).read_to_end(&mut buf)? as u64;
+    if read != len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "length-prefixed payload was shorter than its declared length",
+        ));
+    }
+    Ok(buf)
+}
+
+fn unexpected_eof_to_io(e: std::string::FromUtf8Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+// A declared element/field count from the wire is also untrusted: using
+// it directly as a `Vec::with_capacity` argument would let a crafted
+// count (e.g. `u64::MAX`) trigger an out-of-memory abort well before the
+// loop that actually reads each element has a chance to fail on
+// truncated input. Capping the up-front reservation still avoids
+// reallocation for any real-world document.
+fn capacity_hint(count: u64) -> usize {
+    count.min(4096) as usize
+}
+
+// Encodes one value into an in-memory buffer, bottom-up: every
+// `visited_*` call returns the fully-encoded bytes for its subtree, and a
+// composite just concatenates its children's bytes behind its own tag and
+// count. Building each subtree as a `Vec<u8>` (rather than writing
+// straight to a shared `Write`r) is what lets this be a plain, `Clone`
+// (zero-state) `Visitor` like every other one in this crate.
+#[derive(Clone)]
+struct BinEncodeVisitor;
+
+impl<Leaf: AsRef<str>> Visitor<Leaf> for BinEncodeVisitor {
+    type Output = Vec<u8>;
+
+    fn visited_primitive(self, prim: Primitive) -> Self::Output {
+        match prim {
+            Primitive::Bool(v) => vec![TAG_BOOL, v as u8],
+            Primitive::I8(v) => [&[TAG_I8][..], &v.to_le_bytes()].concat(),
+            Primitive::I16(v) => [&[TAG_I16][..], &v.to_le_bytes()].concat(),
+            Primitive::I32(v) => [&[TAG_I32][..], &v.to_le_bytes()].concat(),
+            Primitive::I64(v) => [&[TAG_I64][..], &v.to_le_bytes()].concat(),
+            Primitive::U8(v) => vec![TAG_U8, v],
+            Primitive::U16(v) => [&[TAG_U16][..], &v.to_le_bytes()].concat(),
+            Primitive::U32(v) => [&[TAG_U32][..], &v.to_le_bytes()].concat(),
+            Primitive::U64(v) => [&[TAG_U64][..], &v.to_le_bytes()].concat(),
+            Primitive::F32(v) => [&[TAG_F32][..], &v.to_le_bytes()].concat(),
+            Primitive::F64(v) => [&[TAG_F64][..], &v.to_le_bytes()].concat(),
+        }
+    }
+
+    fn visited_leaf(self, leaf: Leaf) -> Self::Output {
+        let mut out = vec![TAG_LEAF];
+        write_bytes_with_len(&mut out, leaf.as_ref().as_bytes()).expect("writes to a Vec never fail");
+        out
+    }
+
+    fn visited_seq(self, elems: Vec<Self::Output>) -> Self::Output {
+        encode_entries(TAG_SEQ, elems)
+    }
+
+    fn visited_tuple(self, elems: Vec<Self::Output>) -> Self::Output {
+        encode_entries(TAG_TUPLE, elems)
+    }
+
+    fn visited_map(self, entries: Vec<(String, Self::Output)>) -> Self::Output {
+        let mut out = vec![TAG_MAP];
+        write_uvarint(&mut out, entries.len() as u64).expect("writes to a Vec never fail");
+        for (key, val) in entries {
+            write_bytes_with_len(&mut out, key.as_bytes()).expect("writes to a Vec never fail");
+            out.extend_from_slice(&val);
+        }
+        out
+    }
+
+    fn visited_struct(self, ty: TypeInfo<'_>, fields: Vec<(String, Self::Output)>) -> Self::Output {
+        let mut out = vec![TAG_STRUCT];
+        write_bytes_with_len(&mut out, ty.name().as_bytes()).expect("writes to a Vec never fail");
+        write_uvarint(&mut out, fields.len() as u64).expect("writes to a Vec never fail");
+        for (key, val) in fields {
+            write_bytes_with_len(&mut out, key.as_bytes()).expect("writes to a Vec never fail");
+            out.extend_from_slice(&val);
+        }
+        out
+    }
+
+    fn visited_array(self, _array: crate::array::CowArray<'_, '_>) -> Self::Output {
+        // No element data is accessible yet (see `graph::ValueVisitor`'s
+        // `visited_array`), so there's nothing to encode -- a bare,
+        // zero-length marker at least lets `BinDeserializer` report a
+        // clear "unsupported" error rather than misinterpreting
+        // whatever bytes would otherwise follow.
+        vec![TAG_ARRAY]
+    }
+
+    fn visited_shared(self, idx: usize, inner: Self::Output) -> Self::Output {
+        let mut out = vec![TAG_SHARED];
+        write_uvarint(&mut out, idx as u64).expect("writes to a Vec never fail");
+        out.extend_from_slice(&inner);
+        out
+    }
+
+    fn visited_ref(self, idx: usize) -> Self::Output {
+        let mut out = vec![TAG_REF];
+        write_uvarint(&mut out, idx as u64).expect("writes to a Vec never fail");
+        out
+    }
+}
+
+fn encode_entries(tag: u8, elems: Vec<Vec<u8>>) -> Vec<u8> {
+    let mut out = vec![tag];
+    write_uvarint(&mut out, elems.len() as u64).expect("writes to a Vec never fail");
+    for elem in elems {
+        out.extend_from_slice(&elem);
+    }
+    out
+}
+
+// Writes values in the tagged binary format described at the top of this
+// module.
+pub struct BinSerializer<W> {
+    writer: W,
+}
+
+impl<W: Write> BinSerializer<W> {
+    pub fn new(writer: W) -> Self {
+        BinSerializer { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl BinSerializer<Vec<u8>> {
+    // Preallocates the output buffer using `g.estimate_size()` so writing
+    // a large document doesn't repeatedly reallocate. The hint is not the
+    // exact encoded size (tags and length prefixes aren't counted) --
+    // `Vec::with_capacity` only avoids realloc up to that point, and the
+    // buffer still grows past it as needed.
+    pub fn with_capacity_for<Leaf>(g: &impl GraphSerialize<Leaf>) -> Self {
+        BinSerializer {
+            writer: Vec::with_capacity(g.estimate_size().bytes),
+        }
+    }
+}
+
+impl<Leaf: AsRef<str>, W: Write> GraphSerializer<Leaf> for BinSerializer<W> {
+    type Error = io::Error;
+
+    fn serialize(&mut self, g: &impl GraphSerialize<Leaf>) -> Result<(), Self::Error> {
+        let bytes = g.visit(BinEncodeVisitor);
+        self.writer.write_all(&bytes)
+    }
+
+    fn serialize_into(&mut self, g: impl GraphSerialize<Leaf>) -> Result<(), Self::Error> {
+        self.serialize(&g)
+    }
+
+    fn serialize_index(&mut self, idx: usize) -> Result<(), Self::Error> {
+        self.writer.write_all(&[TAG_REF])?;
+        write_uvarint(&mut self.writer, idx as u64)
+    }
+}
+
+// Reads values written by `BinSerializer`.
+pub struct BinDeserializer<R> {
+    reader: R,
+}
+
+impl<R: Read> BinDeserializer<R> {
+    pub fn new(reader: R) -> Self {
+        BinDeserializer { reader }
+    }
+
+    fn decode<Leaf: From<String>, V: Visitor<Leaf>>(&mut self, v: V) -> io::Result<V::Output> {
+        let mut tag = [0u8; 1];
+        self.reader.read_exact(&mut tag)?;
+        match tag[0] {
+            TAG_BOOL => {
+                let mut b = [0u8; 1];
+                self.reader.read_exact(&mut b)?;
+                Ok(v.visited_primitive(Primitive::Bool(b[0] != 0)))
+            }
+            TAG_I8 => {
+                let mut b = [0u8; 1];
+                self.reader.read_exact(&mut b)?;
+                Ok(v.visited_primitive(Primitive::I8(i8::from_le_bytes(b))))
+            }
+            TAG_I16 => {
+                let mut b = [0u8; 2];
+                self.reader.read_exact(&mut b)?;
+                Ok(v.visited_primitive(Primitive::I16(i16::from_le_bytes(b))))
+            }
+            TAG_I32 => {
+                let mut b = [0u8; 4];
+                self.reader.read_exact(&mut b)?;
+                Ok(v.visited_primitive(Primitive::I32(i32::from_le_bytes(b))))
+            }
+            TAG_I64 => {
+                let mut b = [0u8; 8];
+                self.reader.read_exact(&mut b)?;
+                Ok(v.visited_primitive(Primitive::I64(i64::from_le_bytes(b))))
+            }
+            TAG_U8 => {
+                let mut b = [0u8; 1];
+                self.reader.read_exact(&mut b)?;
+                Ok(v.visited_primitive(Primitive::U8(b[0])))
+            }
+            TAG_U16 => {
+                let mut b = [0u8; 2];
+                self.reader.read_exact(&mut b)?;
+                Ok(v.visited_primitive(Primitive::U16(u16::from_le_bytes(b))))
+            }
+            TAG_U32 => {
+                let mut b = [0u8; 4];
+                self.reader.read_exact(&mut b)?;
+                Ok(v.visited_primitive(Primitive::U32(u32::from_le_bytes(b))))
+            }
+            TAG_U64 => {
+                let mut b = [0u8; 8];
+                self.reader.read_exact(&mut b)?;
+                Ok(v.visited_primitive(Primitive::U64(u64::from_le_bytes(b))))
+            }
+            TAG_F32 => {
+                let mut b = [0u8; 4];
+                self.reader.read_exact(&mut b)?;
+                Ok(v.visited_primitive(Primitive::F32(f32::from_le_bytes(b))))
+            }
+            TAG_F64 => {
+                let mut b = [0u8; 8];
+                self.reader.read_exact(&mut b)?;
+                Ok(v.visited_primitive(Primitive::F64(f64::from_le_bytes(b))))
+            }
+            TAG_LEAF => {
+                let bytes = read_bytes_with_len(&mut self.reader)?;
+                let s = String::from_utf8(bytes).map_err(unexpected_eof_to_io)?;
+                Ok(v.visited_leaf(Leaf::from(s)))
+            }
+            TAG_SEQ => {
+                let count = read_uvarint(&mut self.reader)?;
+                let mut elems = Vec::with_capacity(capacity_hint(count));
+                for _ in 0..count {
+                    elems.push(self.decode(v.clone())?);
+                }
+                Ok(v.visited_seq(elems))
+            }
+            TAG_TUPLE => {
+                let count = read_uvarint(&mut self.reader)?;
+                let mut elems = Vec::with_capacity(capacity_hint(count));
+                for _ in 0..count {
+                    elems.push(self.decode(v.clone())?);
+                }
+                Ok(v.visited_tuple(elems))
+            }
+            TAG_MAP => {
+                let count = read_uvarint(&mut self.reader)?;
+                let mut entries = Vec::with_capacity(capacity_hint(count));
+                for _ in 0..count {
+                    let key_bytes = read_bytes_with_len(&mut self.reader)?;
+                    let key = String::from_utf8(key_bytes).map_err(unexpected_eof_to_io)?;
+                    let val = self.decode(v.clone())?;
+                    entries.push((key, val));
+                }
+                Ok(v.visited_map(entries))
+            }
+            TAG_STRUCT => {
+                let name_bytes = read_bytes_with_len(&mut self.reader)?;
+                let name = String::from_utf8(name_bytes).map_err(unexpected_eof_to_io)?;
+                let count = read_uvarint(&mut self.reader)?;
+                let mut fields = Vec::with_capacity(capacity_hint(count));
+                for _ in 0..count {
+                    let key_bytes = read_bytes_with_len(&mut self.reader)?;
+                    let key = String::from_utf8(key_bytes).map_err(unexpected_eof_to_io)?;
+                    let val = self.decode(v.clone())?;
+                    fields.push((key, val));
+                }
+                Ok(v.visited_struct(TypeInfo::owned(name), fields))
+            }
+            TAG_ARRAY => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "array decoding is unsupported: array::ArrayView has no backing storage upstream",
+            )),
+            TAG_SHARED => {
+                let idx = read_uvarint(&mut self.reader)? as usize;
+                let inner = self.decode(v.clone())?;
+                Ok(v.visited_shared(idx, inner))
+            }
+            TAG_REF => {
+                let idx = read_uvarint(&mut self.reader)? as usize;
+                Ok(v.visited_ref(idx))
+            }
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown tag byte {other}"),
+            )),
+        }
+    }
+}
+
+impl<Leaf: From<String>, R: Read> GraphDeserializer<Leaf> for BinDeserializer<R> {
+    type Err = io::Error;
+
+    fn deserialize<V: Visitor<Leaf>>(
+        &mut self,
+        _t: &impl crate::graph::GraphModel<Leaf>,
+        v: V,
+    ) -> Result<V::Output, Self::Err> {
+        self.decode(v)
+    }
+
+    fn deserialize_index(&mut self) -> Result<usize, Self::Err> {
+        let mut tag = [0u8; 1];
+        self.reader.read_exact(&mut tag)?;
+        if tag[0] != TAG_REF {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("expected a ref tag, found tag byte {}", tag[0]),
+            ));
+        }
+        Ok(read_uvarint(&mut self.reader)? as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{GraphModel, NoModel, RefResolver, RefTable, Value, ValueVisitor};
+
+    fn round_trip(value: &Value<'static, 'static, String>) -> Value<'static, 'static, String> {
+        let mut buf = BinSerializer::with_capacity_for(value);
+        buf.serialize(value).expect("serialize");
+        let bytes = buf.into_inner();
+        let mut de = BinDeserializer::new(bytes.as_slice());
+        de.deserialize(&NoModel, ValueVisitor::new()).expect("deserialize")
+    }
+
+    #[test]
+    fn primitives_round_trip() {
+        let cases = vec![
+            Value::Primitive(Primitive::Bool(true)),
+            Value::Primitive(Primitive::I32(-42)),
+            Value::Primitive(Primitive::U64(u64::MAX)),
+            Value::Primitive(Primitive::F64(std::f64::consts::PI)),
+        ];
+        for case in cases {
+            assert_eq!(round_trip(&case), case);
+        }
+    }
+
+    #[test]
+    fn struct_with_leaves_and_nested_list_round_trips() {
+        let value = Value::Struct {
+            r#type: TypeInfo::owned("Point"),
+            fields: vec![
+                ("x".to_string(), Value::Primitive(Primitive::I32(1))),
+                (
+                    "tags".to_string(),
+                    Value::List(vec![
+                        Value::Leaf("a".to_string()),
+                        Value::Leaf("b".to_string()),
+                    ]),
+                ),
+                (
+                    "pair".to_string(),
+                    Value::Tuple(vec![
+                        Value::Primitive(Primitive::Bool(false)),
+                        Value::Primitive(Primitive::U8(9)),
+                    ]),
+                ),
+            ],
+        };
+        assert_eq!(round_trip(&value), value);
+    }
+
+    #[test]
+    fn with_capacity_for_preallocates_using_estimate_size() {
+        let value: Value<'static, 'static, String> = Value::Tuple(vec![
+            Value::Primitive(Primitive::I64(1)),
+            Value::Primitive(Primitive::I64(2)),
+            Value::Primitive(Primitive::I64(3)),
+        ]);
+        let hint = value.estimate_size();
+        let ser = BinSerializer::with_capacity_for(&value);
+        assert!(ser.into_inner().capacity() >= hint.bytes);
+    }
+
+    #[test]
+    fn array_tag_is_a_clean_unsupported_error_not_a_panic() {
+        // Hand-encode a bare array marker the way `BinEncodeVisitor`
+        // would, since there's no way to construct a real `Value::Array`
+        // (the array subsystem has no backing storage to construct one
+        // from upstream).
+        let bytes = vec![TAG_ARRAY];
+        let mut de = BinDeserializer::new(bytes.as_slice());
+        let result: io::Result<Value<'static, 'static, String>> =
+            de.deserialize(&NoModel, ValueVisitor::new());
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn bogus_length_prefix_errors_cleanly_instead_of_over_allocating() {
+        // A leaf tag claiming a multi-gigabyte payload, backed by only a
+        // couple of real bytes -- `read_bytes_with_len` must bail out as
+        // soon as the source runs dry rather than honoring the claimed
+        // length as an allocation request.
+        let mut bytes = vec![TAG_LEAF];
+        write_uvarint(&mut bytes, 1 << 40).unwrap();
+        bytes.extend_from_slice(b"hi");
+        let mut de = BinDeserializer::new(bytes.as_slice());
+        let result: io::Result<Value<'static, 'static, String>> =
+            de.deserialize(&NoModel, ValueVisitor::new());
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    // A trivial `Ref` whose `id()` identifies a shared `Rc`-backed value,
+    // used only to exercise `serialize_ref`/`deserialize_ref`.
+    #[derive(Clone)]
+    struct SharedValue {
+        id: uuid::Uuid,
+        value: std::rc::Rc<Value<'static, 'static, String>>,
+    }
+    impl std::borrow::Borrow<Value<'static, 'static, String>> for SharedValue {
+        fn borrow(&self) -> &Value<'static, 'static, String> {
+            &self.value
+        }
+    }
+    impl crate::graph::Ref<Value<'static, 'static, String>> for SharedValue {
+        fn id(self) -> uuid::Uuid {
+            self.id
+        }
+    }
+
+    #[test]
+    fn serialize_ref_writes_a_shared_subgraph_only_once() {
+        let shared = SharedValue {
+            id: uuid::Uuid::new_v4(),
+            value: std::rc::Rc::new(Value::Primitive(Primitive::I32(7))),
+        };
+
+        let mut ser = BinSerializer::new(Vec::new());
+        let mut table = RefTable::new();
+        // Encode the same shared ref twice -- the second encounter should
+        // only emit its index, not a second copy of the full value.
+        ser.serialize_ref(&mut table, &shared).unwrap();
+        ser.serialize_ref(&mut table, &shared).unwrap();
+        let bytes = ser.into_inner();
+
+        let mut de = BinDeserializer::new(bytes.as_slice());
+        let mut resolver: RefResolver<Value<'static, 'static, String>> = RefResolver::new();
+        let first = de
+            .deserialize_ref(&mut resolver, &NoModel, ValueVisitor::new())
+            .unwrap();
+        let second = de
+            .deserialize_ref(&mut resolver, &NoModel, ValueVisitor::new())
+            .unwrap();
+        assert_eq!(first, Value::Primitive(Primitive::I32(7)));
+        assert_eq!(second, first);
+    }
+
+    #[test]
+    fn shared_and_ref_round_trip_through_the_value_ir() {
+        // A list containing the same shared sub-list twice, encoded via
+        // `Value::Shared`/`Value::Ref` (not `serialize_ref`/`Ref<T>`, which
+        // is the separate model-object mechanism exercised above).
+        let shared_leaf = Value::Shared(
+            0,
+            Box::new(Value::List(vec![Value::Leaf("x".to_string())])),
+        );
+        let value: Value<'static, 'static, String> =
+            Value::List(vec![shared_leaf, Value::Ref(0)]);
+        assert_eq!(round_trip(&value), value);
+    }
+}
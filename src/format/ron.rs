@@ -0,0 +1,663 @@
+// A compact, human-readable transfer syntax loosely modelled on RON
+// (Rusty Object Notation): primitives are bare literals (`true`,
+// `-42i32`, `3.14f64`), leaves are quoted strings, sequences are
+// `[a, b, c]`, tuples are `(a, b, c)`, maps are `{"k": v, ...}`, and
+// structs are `Name(k: v, ...)`. This is the concrete `GraphSerializer`/
+// `GraphDeserializer` pair `RonFormat` drives (see `src/bindings/mod.rs`),
+// exercising exactly the same `Visitor` walk as `format::bin`.
+
+use crate::graph::{GraphDeserializer, GraphModel, GraphSerialize, GraphSerializer, Visitor};
+use crate::types::{Primitive, TypeInfo};
+use std::io::{self, Read, Write};
+
+// Only escapes what `Parser::parse_quoted_string` actually understands
+// (`"`, `\`, and the three common whitespace escapes); every other
+// character, including control characters and arbitrary Unicode, is
+// written out raw and read back raw by the parser's fallback branch. This
+// deliberately does NOT reuse `{:?}` (`Debug`) escaping, which emits
+// `\0`/`\u{...}` forms the parser has no matching case for.
+fn escape_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Plain `Display` renders NaN/Infinity as `NaN`/`inf`/`-inf`, which the
+// numeric-literal grammar below can't parse as digits -- special-case them
+// to named tokens (`NaNf32`, `inff64`, `-inff64`, ...) that `Parser`
+// recognizes explicitly instead.
+fn format_f32(v: f32) -> String {
+    if v.is_nan() {
+        "NaNf32".to_string()
+    } else if v == f32::INFINITY {
+        "inff32".to_string()
+    } else if v == f32::NEG_INFINITY {
+        "-inff32".to_string()
+    } else {
+        format!("{v}f32")
+    }
+}
+
+fn format_f64(v: f64) -> String {
+    if v.is_nan() {
+        "NaNf64".to_string()
+    } else if v == f64::INFINITY {
+        "inff64".to_string()
+    } else if v == f64::NEG_INFINITY {
+        "-inff64".to_string()
+    } else {
+        format!("{v}f64")
+    }
+}
+
+// Encodes one value into an in-memory `String`, bottom-up -- mirrors
+// `format::bin::BinEncodeVisitor` but produces text instead of bytes.
+#[derive(Clone)]
+struct RonEncodeVisitor;
+
+impl<Leaf: AsRef<str>> Visitor<Leaf> for RonEncodeVisitor {
+    type Output = String;
+
+    fn visited_primitive(self, prim: Primitive) -> Self::Output {
+        match prim {
+            Primitive::Bool(v) => v.to_string(),
+            Primitive::I8(v) => format!("{v}i8"),
+            Primitive::I16(v) => format!("{v}i16"),
+            Primitive::I32(v) => format!("{v}i32"),
+            Primitive::I64(v) => format!("{v}i64"),
+            Primitive::U8(v) => format!("{v}u8"),
+            Primitive::U16(v) => format!("{v}u16"),
+            Primitive::U32(v) => format!("{v}u32"),
+            Primitive::U64(v) => format!("{v}u64"),
+            Primitive::F32(v) => format_f32(v),
+            Primitive::F64(v) => format_f64(v),
+        }
+    }
+
+    fn visited_leaf(self, leaf: Leaf) -> Self::Output {
+        escape_str(leaf.as_ref())
+    }
+
+    fn visited_seq(self, elems: Vec<Self::Output>) -> Self::Output {
+        format!("[{}]", elems.join(", "))
+    }
+
+    fn visited_tuple(self, elems: Vec<Self::Output>) -> Self::Output {
+        format!("({})", elems.join(", "))
+    }
+
+    fn visited_map(self, entries: Vec<(String, Self::Output)>) -> Self::Output {
+        let body = entries
+            .into_iter()
+            .map(|(k, v)| format!("{}: {}", escape_str(&k), v))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{{{body}}}")
+    }
+
+    fn visited_struct(self, ty: TypeInfo<'_>, fields: Vec<(String, Self::Output)>) -> Self::Output {
+        let body = fields
+            .into_iter()
+            .map(|(k, v)| format!("{k}: {v}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{}({})", ty.name(), body)
+    }
+
+    fn visited_array(self, _array: crate::array::CowArray<'_, '_>) -> Self::Output {
+        // See `format::bin::BinEncodeVisitor::visited_array`: no element
+        // data is accessible yet, so this is a bare marker that
+        // `RonDeserializer` reports as a clear "unsupported" error.
+        "Array()".to_string()
+    }
+
+    fn visited_shared(self, idx: usize, inner: Self::Output) -> Self::Output {
+        format!("&{idx}({inner})")
+    }
+
+    fn visited_ref(self, idx: usize) -> Self::Output {
+        format!("*{idx}")
+    }
+}
+
+// Writes values in the textual format described at the top of this
+// module.
+pub struct RonSerializer<W> {
+    writer: W,
+}
+
+impl<W: Write> RonSerializer<W> {
+    pub fn new(writer: W) -> Self {
+        RonSerializer { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl RonSerializer<Vec<u8>> {
+    // Preallocates the output buffer using `g.estimate_size()`, same
+    // rationale as `BinSerializer::with_capacity_for`.
+    pub fn with_capacity_for<Leaf>(g: &impl GraphSerialize<Leaf>) -> Self {
+        RonSerializer {
+            writer: Vec::with_capacity(g.estimate_size().bytes),
+        }
+    }
+}
+
+impl<Leaf: AsRef<str>, W: Write> GraphSerializer<Leaf> for RonSerializer<W> {
+    type Error = io::Error;
+
+    fn serialize(&mut self, g: &impl GraphSerialize<Leaf>) -> Result<(), Self::Error> {
+        let text = g.visit(RonEncodeVisitor);
+        self.writer.write_all(text.as_bytes())
+    }
+
+    fn serialize_into(&mut self, g: impl GraphSerialize<Leaf>) -> Result<(), Self::Error> {
+        self.serialize(&g)
+    }
+
+    fn serialize_index(&mut self, idx: usize) -> Result<(), Self::Error> {
+        write!(self.writer, "#{idx}")
+    }
+}
+
+// Reads values written by `RonSerializer`. Unlike `BinDeserializer`, this
+// reads its whole source up front: the grammar isn't self-delimiting in a
+// streaming-friendly way (e.g. a bare number has no length prefix), so
+// there's no way to know a value has ended without looking past it.
+pub struct RonDeserializer<R> {
+    reader: R,
+}
+
+impl<R: Read> RonDeserializer<R> {
+    pub fn new(reader: R) -> Self {
+        RonDeserializer { reader }
+    }
+
+    fn decode<Leaf: From<String>, V: Visitor<Leaf>>(&mut self, v: V) -> io::Result<V::Output> {
+        let mut text = String::new();
+        self.reader.read_to_string(&mut text)?;
+        let mut p = Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+        };
+        let out = p
+            .parse_value(v)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(out)
+    }
+}
+
+impl<Leaf: From<String>, R: Read> GraphDeserializer<Leaf> for RonDeserializer<R> {
+    type Err = io::Error;
+
+    fn deserialize<V: Visitor<Leaf>>(
+        &mut self,
+        _t: &impl crate::graph::GraphModel<Leaf>,
+        v: V,
+    ) -> Result<V::Output, Self::Err> {
+        self.decode(v)
+    }
+
+    fn deserialize_index(&mut self) -> Result<usize, Self::Err> {
+        let mut text = String::new();
+        self.reader.read_to_string(&mut text)?;
+        let mut p = Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+        };
+        p.skip_ws();
+        if p.peek() != Some('#') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "expected a '#<index>' ref marker",
+            ));
+        }
+        p.pos += 1;
+        let digits = p.take_while(|c| c.is_ascii_digit());
+        digits
+            .parse::<usize>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if pred(c)) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected '{c}' at position {}", self.pos))
+        }
+    }
+
+    fn parse_ident(&mut self) -> String {
+        self.take_while(|c| c.is_alphanumeric() || c == '_')
+    }
+
+    fn parse_quoted_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.peek() {
+                None => return Err("unterminated string literal".to_string()),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('n') => out.push('\n'),
+                        Some('t') => out.push('\t'),
+                        Some('r') => out.push('\r'),
+                        Some(c @ ('"' | '\\')) => out.push(c),
+                        Some(c) => return Err(format!("unknown escape '\\{c}'")),
+                        None => return Err("unterminated escape sequence".to_string()),
+                    }
+                    self.pos += 1;
+                }
+                Some(c) => {
+                    out.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn parse_value<Leaf: From<String>, V: Visitor<Leaf>>(
+        &mut self,
+        v: V,
+    ) -> Result<V::Output, String> {
+        self.skip_ws();
+        match self.peek() {
+            Some('"') => {
+                let s = self.parse_quoted_string()?;
+                Ok(v.visited_leaf(Leaf::from(s)))
+            }
+            Some('[') => {
+                self.pos += 1;
+                let mut elems = Vec::new();
+                self.skip_ws();
+                if self.peek() == Some(']') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        elems.push(self.parse_value(v.clone())?);
+                        self.skip_ws();
+                        match self.peek() {
+                            Some(',') => {
+                                self.pos += 1;
+                                self.skip_ws();
+                                if self.peek() == Some(']') {
+                                    self.pos += 1;
+                                    break;
+                                }
+                            }
+                            Some(']') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => return Err(format!("expected ',' or ']' at position {}", self.pos)),
+                        }
+                    }
+                }
+                Ok(v.visited_seq(elems))
+            }
+            Some('(') => {
+                self.pos += 1;
+                let mut elems = Vec::new();
+                self.skip_ws();
+                if self.peek() == Some(')') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        elems.push(self.parse_value(v.clone())?);
+                        self.skip_ws();
+                        match self.peek() {
+                            Some(',') => {
+                                self.pos += 1;
+                                self.skip_ws();
+                                if self.peek() == Some(')') {
+                                    self.pos += 1;
+                                    break;
+                                }
+                            }
+                            Some(')') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => return Err(format!("expected ',' or ')' at position {}", self.pos)),
+                        }
+                    }
+                }
+                Ok(v.visited_tuple(elems))
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut entries = Vec::new();
+                self.skip_ws();
+                if self.peek() == Some('}') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        self.skip_ws();
+                        let key = self.parse_quoted_string()?;
+                        self.skip_ws();
+                        self.expect(':')?;
+                        let val = self.parse_value(v.clone())?;
+                        entries.push((key, val));
+                        self.skip_ws();
+                        match self.peek() {
+                            Some(',') => {
+                                self.pos += 1;
+                                self.skip_ws();
+                                if self.peek() == Some('}') {
+                                    self.pos += 1;
+                                    break;
+                                }
+                            }
+                            Some('}') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => return Err(format!("expected ',' or '}}' at position {}", self.pos)),
+                        }
+                    }
+                }
+                Ok(v.visited_map(entries))
+            }
+            Some('&') => {
+                self.pos += 1;
+                let digits = self.take_while(|c| c.is_ascii_digit());
+                let idx: usize = digits
+                    .parse()
+                    .map_err(|e| format!("invalid shared-value index: {e}"))?;
+                self.expect('(')?;
+                let inner = self.parse_value(v.clone())?;
+                self.skip_ws();
+                self.expect(')')?;
+                Ok(v.visited_shared(idx, inner))
+            }
+            Some('*') => {
+                self.pos += 1;
+                let digits = self.take_while(|c| c.is_ascii_digit());
+                let idx: usize = digits
+                    .parse()
+                    .map_err(|e| format!("invalid reference index: {e}"))?;
+                Ok(v.visited_ref(idx))
+            }
+            Some('-') if matches!(self.chars.get(self.pos + 1), Some(c) if c.is_alphabetic()) => {
+                self.pos += 1;
+                let word = self.parse_ident();
+                let prim = match word.as_str() {
+                    "inff32" => Primitive::F32(f32::NEG_INFINITY),
+                    "inff64" => Primitive::F64(f64::NEG_INFINITY),
+                    other => return Err(format!("unknown numeric token '-{other}'")),
+                };
+                Ok(v.visited_primitive(prim))
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let num = self.take_while(|c| c.is_ascii_digit() || c == '-' || c == '.');
+                let suffix = self.take_while(|c| c.is_alphanumeric());
+                let prim = match suffix.as_str() {
+                    "i8" => Primitive::I8(num.parse().map_err(|e| format!("{e}"))?),
+                    "i16" => Primitive::I16(num.parse().map_err(|e| format!("{e}"))?),
+                    "i32" => Primitive::I32(num.parse().map_err(|e| format!("{e}"))?),
+                    "i64" => Primitive::I64(num.parse().map_err(|e| format!("{e}"))?),
+                    "u8" => Primitive::U8(num.parse().map_err(|e| format!("{e}"))?),
+                    "u16" => Primitive::U16(num.parse().map_err(|e| format!("{e}"))?),
+                    "u32" => Primitive::U32(num.parse().map_err(|e| format!("{e}"))?),
+                    "u64" => Primitive::U64(num.parse().map_err(|e| format!("{e}"))?),
+                    "f32" => Primitive::F32(num.parse().map_err(|e| format!("{e}"))?),
+                    "f64" => Primitive::F64(num.parse().map_err(|e| format!("{e}"))?),
+                    other => return Err(format!("unknown numeric suffix '{other}'")),
+                };
+                Ok(v.visited_primitive(prim))
+            }
+            Some(c) if c.is_alphabetic() => {
+                let name = self.parse_ident();
+                if name == "true" {
+                    return Ok(v.visited_primitive(Primitive::Bool(true)));
+                }
+                if name == "false" {
+                    return Ok(v.visited_primitive(Primitive::Bool(false)));
+                }
+                match name.as_str() {
+                    "inff32" => return Ok(v.visited_primitive(Primitive::F32(f32::INFINITY))),
+                    "inff64" => return Ok(v.visited_primitive(Primitive::F64(f64::INFINITY))),
+                    "NaNf32" => return Ok(v.visited_primitive(Primitive::F32(f32::NAN))),
+                    "NaNf64" => return Ok(v.visited_primitive(Primitive::F64(f64::NAN))),
+                    _ => {}
+                }
+                self.skip_ws();
+                self.expect('(')?;
+                if name == "Array" {
+                    self.skip_ws();
+                    self.expect(')')?;
+                    return Err("array decoding is unsupported: array::ArrayView has no backing \
+                                storage upstream"
+                        .to_string());
+                }
+                let mut fields = Vec::new();
+                self.skip_ws();
+                if self.peek() == Some(')') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        self.skip_ws();
+                        let key = self.parse_ident();
+                        self.skip_ws();
+                        self.expect(':')?;
+                        let val = self.parse_value(v.clone())?;
+                        fields.push((key, val));
+                        self.skip_ws();
+                        match self.peek() {
+                            Some(',') => {
+                                self.pos += 1;
+                                self.skip_ws();
+                                if self.peek() == Some(')') {
+                                    self.pos += 1;
+                                    break;
+                                }
+                            }
+                            Some(')') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => return Err(format!("expected ',' or ')' at position {}", self.pos)),
+                        }
+                    }
+                }
+                Ok(v.visited_struct(TypeInfo::owned(name), fields))
+            }
+            Some(c) => Err(format!("unexpected character '{c}' at position {}", self.pos)),
+            None => Err("unexpected end of input".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{NoModel, Value, ValueVisitor};
+
+    fn round_trip(value: &Value<'static, 'static, String>) -> Value<'static, 'static, String> {
+        let mut ser = RonSerializer::with_capacity_for(value);
+        ser.serialize(value).expect("serialize");
+        let bytes = ser.into_inner();
+        let mut de = RonDeserializer::new(bytes.as_slice());
+        de.deserialize(&NoModel, ValueVisitor::new()).expect("deserialize")
+    }
+
+    #[test]
+    fn primitives_round_trip() {
+        let cases = vec![
+            Value::Primitive(Primitive::Bool(true)),
+            Value::Primitive(Primitive::I32(-42)),
+            Value::Primitive(Primitive::U64(u64::MAX)),
+            Value::Primitive(Primitive::F64(std::f64::consts::PI)),
+        ];
+        for case in cases {
+            assert_eq!(round_trip(&case), case);
+        }
+    }
+
+    #[test]
+    fn struct_with_leaves_and_nested_list_round_trips() {
+        let value = Value::Struct {
+            r#type: TypeInfo::owned("Point"),
+            fields: vec![
+                ("x".to_string(), Value::Primitive(Primitive::I32(1))),
+                (
+                    "tags".to_string(),
+                    Value::List(vec![
+                        Value::Leaf("a".to_string()),
+                        Value::Leaf("b \"quoted\"".to_string()),
+                    ]),
+                ),
+                (
+                    "pair".to_string(),
+                    Value::Tuple(vec![
+                        Value::Primitive(Primitive::Bool(false)),
+                        Value::Primitive(Primitive::U8(9)),
+                    ]),
+                ),
+            ],
+        };
+        assert_eq!(round_trip(&value), value);
+    }
+
+    #[test]
+    fn map_with_escaped_keys_round_trips() {
+        let value: Value<'static, 'static, String> = Value::Map(vec![
+            ("a key".to_string(), Value::Primitive(Primitive::I64(1))),
+            ("b\"k".to_string(), Value::Primitive(Primitive::I64(2))),
+        ]);
+        assert_eq!(round_trip(&value), value);
+    }
+
+    #[test]
+    fn with_capacity_for_preallocates_using_estimate_size() {
+        let value: Value<'static, 'static, String> = Value::Tuple(vec![
+            Value::Primitive(Primitive::I64(1)),
+            Value::Primitive(Primitive::I64(2)),
+        ]);
+        let hint = value.estimate_size();
+        let ser = RonSerializer::with_capacity_for(&value);
+        assert!(ser.into_inner().capacity() >= hint.bytes);
+    }
+
+    #[test]
+    fn leaf_with_control_characters_round_trips() {
+        // Regression test: `escape_str` must not lean on `{:?}` (Debug)
+        // escaping, which emits `\0`/`\u{...}` forms `parse_quoted_string`
+        // doesn't understand -- NUL, DEL, and other non-ASCII characters
+        // should just pass through raw instead.
+        let value: Value<'static, 'static, String> =
+            Value::Leaf("nul:\u{0}del:\u{7f}zwj:\u{200d}end".to_string());
+        assert_eq!(round_trip(&value), value);
+    }
+
+    #[test]
+    fn special_floats_round_trip() {
+        for case in [
+            Value::Primitive(Primitive::F64(f64::NAN)),
+            Value::Primitive(Primitive::F64(f64::INFINITY)),
+            Value::Primitive(Primitive::F64(f64::NEG_INFINITY)),
+            Value::Primitive(Primitive::F32(f32::NAN)),
+            Value::Primitive(Primitive::F32(f32::INFINITY)),
+            Value::Primitive(Primitive::F32(f32::NEG_INFINITY)),
+        ] {
+            let got = round_trip(&case);
+            match (case, got) {
+                (
+                    Value::Primitive(Primitive::F64(want)),
+                    Value::Primitive(Primitive::F64(got)),
+                ) => assert!(want.is_nan() && got.is_nan() || want == got),
+                (
+                    Value::Primitive(Primitive::F32(want)),
+                    Value::Primitive(Primitive::F32(got)),
+                ) => assert!(want.is_nan() && got.is_nan() || want == got),
+                (want, got) => panic!("expected {want:?}, got {got:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn array_marker_is_a_clean_unsupported_error_not_a_panic() {
+        let mut de = RonDeserializer::new("Array()".as_bytes());
+        let result: io::Result<Value<'static, 'static, String>> =
+            de.deserialize(&NoModel, ValueVisitor::new());
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn bin_and_ron_agree_on_the_same_value() {
+        use crate::format::bin::{BinDeserializer, BinSerializer};
+
+        let value = Value::Struct {
+            r#type: TypeInfo::owned("Mixed"),
+            fields: vec![
+                ("n".to_string(), Value::Primitive(Primitive::I32(7))),
+                ("s".to_string(), Value::Leaf("hi".to_string())),
+            ],
+        };
+
+        let mut bin_ser = BinSerializer::with_capacity_for(&value);
+        bin_ser.serialize(&value).unwrap();
+        let bin_bytes = bin_ser.into_inner();
+        let mut bin_de = BinDeserializer::new(bin_bytes.as_slice());
+        let via_bin: Value<'static, 'static, String> =
+            bin_de.deserialize(&NoModel, ValueVisitor::new()).unwrap();
+
+        assert_eq!(via_bin, round_trip(&value));
+    }
+
+    #[test]
+    fn shared_and_ref_round_trip() {
+        let shared_leaf = Value::Shared(
+            0,
+            Box::new(Value::List(vec![Value::Leaf("x".to_string())])),
+        );
+        let value: Value<'static, 'static, String> =
+            Value::List(vec![shared_leaf, Value::Ref(0)]);
+        assert_eq!(round_trip(&value), value);
+    }
+}
@@ -1,6 +1,7 @@
-use crate::array::{Array, ArrayView, CowArray, Shape};
-use crate::types::{AsPrimitiveType, Primitive, PrimitiveType, TypeInfo};
-use std::borrow::{Borrow, Cow};
+use crate::array::CowArray;
+use crate::types::{Primitive, PrimitiveType, TypeInfo};
+use std::borrow::Borrow;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 // A Ref is anything that is Borrowable as a type T
@@ -19,12 +20,43 @@ pub trait ModelVisitor<Leaf> {
     fn visited_leaf_ref(self);
 }
 
-pub trait Visitor<Leaf> {
+// `Clone` is required so that a composite value (seq/map/tuple/struct)
+// can hand a fresh copy of the visitor down to each child and still have
+// one left over for its own `visited_*` call -- every `Visitor` used in
+// this crate is a cheap, state-free handle, so this is never more than a
+// pointer-sized copy.
+//
+// `Leaf` is whatever representation the model actually hands over -- it
+// need not be an owned type: a model can set `Leaf = &'a str` to visit
+// borrowed slices with no cloning at all. There is deliberately no
+// separate "borrowed vs. owned" associated type here (as there once was,
+// `Visitor::LeafRef`): a generic `visited_leaf` body has
+// no way to produce a value of an opaque associated type it's merely
+// bounded by `ToOwned<Owned = Leaf>`, so that design was unimplementable
+// by any real visitor. `Leaf` being the trait's own type parameter, taken
+// directly, is exactly serde's `Serializer::serialize_str(self, &str)`
+// shape and is what every `visited_leaf` body below actually needs.
+pub trait Visitor<Leaf>: Clone {
     type Output;
-    type LeafRef: ToOwned<Owned = Leaf>;
 
     fn visited_primitive(self, prim: Primitive) -> Self::Output;
-    fn visited_leaf(self, leaf: Self::LeafRef) -> Self::Output;
+    fn visited_leaf(self, leaf: Leaf) -> Self::Output;
+    fn visited_seq(self, elems: Vec<Self::Output>) -> Self::Output;
+    fn visited_tuple(self, elems: Vec<Self::Output>) -> Self::Output;
+    fn visited_map(self, entries: Vec<(String, Self::Output)>) -> Self::Output;
+    fn visited_struct(self, ty: TypeInfo<'_>, fields: Vec<(String, Self::Output)>) -> Self::Output;
+    fn visited_array(self, array: CowArray<'_, '_>) -> Self::Output;
+
+    // The first materialization of a value that is referenced more than
+    // once (a shared subgraph, or the entry point of what would otherwise
+    // be a cycle): tagged with the index `idx` that later `visited_ref`
+    // occurrences of the same object point back to.
+    fn visited_shared(self, idx: usize, inner: Self::Output) -> Self::Output;
+
+    // A later occurrence of an already-`visited_shared` object, referring
+    // back to it by `idx` instead of re-materializing (or, for a cycle,
+    // recursing forever).
+    fn visited_ref(self, idx: usize) -> Self::Output;
 }
 
 pub trait IntoVisitor<Leaf> {
@@ -34,28 +66,93 @@ pub trait IntoVisitor<Leaf> {
     fn visited_leaf(self, leaf: Leaf) -> Self::Output;
 }
 
+// A rough byte-size estimate for a model, borrowing the idea from PDF's
+// `DataSize`-style estimation. Used to preallocate a serializer's output
+// buffer before actually writing anything. It sums raw value widths only
+// (no format's tags, length prefixes or text formatting), so it is not
+// the exact encoded size of any format and can be above or below it.
+#[derive(Debug, Clone, Copy)]
+pub struct SizeHint {
+    pub bytes: usize,
+    // Set once any part of the walk hits something whose size isn't known
+    // ahead of time (an `array::Dim::Var`/`Dim::Jagged` dimension, or an
+    // array with no accessible shape); that part then only contributes a
+    // lower bound to `bytes`.
+    pub is_dynamic: bool,
+}
+
+impl SizeHint {
+    pub fn fixed(bytes: usize) -> Self {
+        SizeHint {
+            bytes,
+            is_dynamic: false,
+        }
+    }
+
+    pub fn combine(self, other: SizeHint) -> SizeHint {
+        SizeHint {
+            bytes: self.bytes + other.bytes,
+            is_dynamic: self.is_dynamic || other.is_dynamic,
+        }
+    }
+}
+
+impl Default for SizeHint {
+    fn default() -> Self {
+        SizeHint::fixed(0)
+    }
+}
+
 // Graph models, GraphSerialize, and GraphDeserialize
 pub trait GraphModel<Leaf> {
     fn visit_model<V: ModelVisitor<Leaf>>(&self, v: V) -> V::Output;
+
+    // Walks the model and sums a conservative byte estimate: fixed
+    // widths for primitives, element-count * element-size for arrays
+    // (via `array::Shape::estimate_size`), and recursive sums for
+    // containers.
+    fn estimate_size(&self) -> SizeHint;
 }
 
 pub trait GraphSerialize<Leaf>: GraphModel<Leaf> {
-    fn visit<R, V>(&self, v: V) -> V::Output
-    where
-        R: ToOwned<Owned = Leaf>,
-        V: Visitor<R>;
+    fn visit<V: Visitor<Leaf>>(&self, v: V) -> V::Output;
 
-    fn visit_into<R, V>(self, v: V) -> V::Output
+    fn visit_into<V: IntoVisitor<Leaf>>(self, v: V) -> V::Output
     where
-        R: ToOwned<Owned = Leaf>,
-        V: IntoVisitor<R>;
+        Self: Sized;
 }
 
 pub trait GraphSerializer<Leaf> {
     type Error;
 
+    // `serialize` writes into the serializer's existing writer and does not
+    // reserve space itself; the `format` serializers that write into a
+    // `Vec<u8>` size it from `g.estimate_size()` in `with_capacity_for`.
     fn serialize(&mut self, g: &impl GraphSerialize<Leaf>) -> Result<(), Self::Error>;
     fn serialize_into(&mut self, g: impl GraphSerialize<Leaf>) -> Result<(), Self::Error>;
+
+    // Writes the integer index assigned to an indirect object, so the
+    // decoder can tell an inline value and a back-reference to index `N`
+    // apart.
+    fn serialize_index(&mut self, idx: usize) -> Result<(), Self::Error>;
+
+    // Serializes `r` as an indirect object: the first time `r`'s id is
+    // reserved in `table` the full object is written out and indexed;
+    // every later reference to the same id just re-emits the index, so a
+    // shared subgraph is written once and a cycle back to an
+    // already-reserved id terminates instead of recursing forever.
+    fn serialize_ref<R, T>(&mut self, table: &mut RefTable, r: &R) -> Result<(), Self::Error>
+    where
+        R: Ref<T> + Clone,
+        T: GraphSerialize<Leaf>,
+    {
+        let (idx, first) = table.reserve(r.clone().id());
+        self.serialize_index(idx)?;
+        if first {
+            self.serialize(r.borrow())?;
+        }
+        Ok(())
+    }
 }
 
 pub trait GraphDeserializer<Leaf> {
@@ -66,129 +163,670 @@ pub trait GraphDeserializer<Leaf> {
         t: &impl GraphModel<Leaf>,
         v: V,
     ) -> Result<V::Output, Self::Err>;
+
+    // Reads the index written by `GraphSerializer::serialize_index`.
+    fn deserialize_index(&mut self) -> Result<usize, Self::Err>;
+
+    // The inverse of `serialize_ref`: reads an index and, the first time
+    // it's seen, decodes and registers the object in `resolver`; a later
+    // encounter of the same index is resolved from `resolver` instead of
+    // reading (and re-decoding) it again.
+    fn deserialize_ref<T>(
+        &mut self,
+        resolver: &mut RefResolver<T>,
+        t: &impl GraphModel<Leaf>,
+        v: impl Visitor<Leaf, Output = T>,
+    ) -> Result<T, Self::Err>
+    where
+        T: Clone,
+    {
+        let idx = self.deserialize_index()?;
+        if let Some(existing) = resolver.get(idx) {
+            return Ok(existing.clone());
+        }
+        let value = self.deserialize(t, v)?;
+        resolver.insert(idx, value.clone());
+        Ok(value)
+    }
+}
+
+// Placeholder model passed to `GraphDeserializer::deserialize` when the
+// decoder is fully self-describing (e.g. `ContentDeserializer`,
+// `format::bin::BinDeserializer`) and so never actually consults the
+// model argument for type information.
+pub struct NoModel;
+
+impl<Leaf> GraphModel<Leaf> for NoModel {
+    fn visit_model<V: ModelVisitor<Leaf>>(&self, _v: V) -> V::Output {
+        unreachable!("NoModel carries no type information to visit")
+    }
+
+    fn estimate_size(&self) -> SizeHint {
+        SizeHint::default()
+    }
+}
+
+#[derive(Debug)]
+pub enum TranscodeError<DErr, SErr> {
+    Deserialize(DErr),
+    Serialize(SErr),
+}
+
+// Transcodes `de` straight into `ser` without the caller ever touching a
+// concrete Rust model type: decoded into the self-describing `Value` IR
+// (so the two formats don't need to agree on anything but that IR) and
+// immediately re-serialized. `NoModel` is passed to `deserialize` since
+// every format this crate ships is fully self-describing.
+pub fn transcode<Leaf, D, S>(de: &mut D, ser: &mut S) -> Result<(), TranscodeError<D::Err, S::Error>>
+where
+    Leaf: Clone,
+    D: GraphDeserializer<Leaf>,
+    S: GraphSerializer<Leaf>,
+{
+    let value: Value<'static, 'static, Leaf> = de
+        .deserialize(&NoModel, ValueVisitor::new())
+        .map_err(TranscodeError::Deserialize)?;
+    ser.serialize(&value).map_err(TranscodeError::Serialize)
+}
+
+// A fully self-describing, dynamically-typed intermediate representation
+// of a model value -- unlike `Content`, which only buffers one value for
+// re-dispatch, `Value` is meant to stand in for the model itself (e.g. so
+// Python bindings can hand a value back without reconstructing the
+// original Rust type).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a, 'ctx, Leaf> {
+    Primitive(Primitive),
+    Leaf(Leaf),
+    Array(CowArray<'a, 'ctx>),
+    List(Vec<Value<'a, 'ctx, Leaf>>),
+    Tuple(Vec<Value<'a, 'ctx, Leaf>>),
+    Map(Vec<(String, Value<'a, 'ctx, Leaf>)>),
+    Struct {
+        r#type: TypeInfo<'a>,
+        fields: Vec<(String, Value<'a, 'ctx, Leaf>)>,
+    },
+    // A value referenced more than once (a shared subgraph) or that would
+    // otherwise form a cycle: `idx` is the index later `Ref` occurrences
+    // of the same underlying object point back to. This is what lets
+    // `Value` -- the dynamic IR every format in this crate ultimately
+    // walks -- actually carry the indirect-object sharing `RefTable`/
+    // `RefResolver` model, rather than that machinery only being
+    // reachable through its own unit tests.
+    Shared(usize, Box<Value<'a, 'ctx, Leaf>>),
+    Ref(usize),
+}
+
+impl<'a, 'ctx, Leaf: Clone> GraphModel<Leaf> for Value<'a, 'ctx, Leaf> {
+    fn visit_model<V: ModelVisitor<Leaf>>(&self, _v: V) -> V::Output {
+        todo!("type-only introspection of a dynamic Value isn't meaningful without a schema")
+    }
+
+    fn estimate_size(&self) -> SizeHint {
+        match self {
+            Value::Primitive(p) => p.estimate_size(),
+            Value::Leaf(_) => SizeHint::default(),
+            // `array::ArrayView` has no accessible element storage yet,
+            // so there's nothing to size beyond "at least zero bytes".
+            Value::Array(_) => SizeHint {
+                bytes: 0,
+                is_dynamic: true,
+            },
+            Value::List(items) | Value::Tuple(items) => items
+                .iter()
+                .fold(SizeHint::fixed(0), |acc, v| acc.combine(v.estimate_size())),
+            Value::Map(entries) => entries.iter().fold(SizeHint::fixed(0), |acc, (k, v)| {
+                acc.combine(SizeHint::fixed(k.len())).combine(v.estimate_size())
+            }),
+            Value::Struct { fields, .. } => fields.iter().fold(SizeHint::fixed(0), |acc, (k, v)| {
+                acc.combine(SizeHint::fixed(k.len())).combine(v.estimate_size())
+            }),
+            // A conservative guess for the index itself -- the exact
+            // encoding (a varint, a bare integer literal, ...) is a format
+            // concern, not something this model-level estimate needs to
+            // match precisely.
+            Value::Shared(_, inner) => inner.estimate_size().combine(SizeHint::fixed(8)),
+            Value::Ref(_) => SizeHint::fixed(8),
+        }
+    }
+}
+
+impl<'a, 'ctx, Leaf: Clone> GraphSerialize<Leaf> for Value<'a, 'ctx, Leaf> {
+    fn visit<V: Visitor<Leaf>>(&self, v: V) -> V::Output {
+        match self {
+            Value::Primitive(p) => v.visited_primitive(*p),
+            Value::Leaf(l) => v.visited_leaf(l.clone()),
+            Value::Array(a) => v.visited_array(a.clone()),
+            Value::List(items) => {
+                let out = items.iter().map(|item| item.visit(v.clone())).collect();
+                v.visited_seq(out)
+            }
+            Value::Tuple(items) => {
+                let out = items.iter().map(|item| item.visit(v.clone())).collect();
+                v.visited_tuple(out)
+            }
+            Value::Map(entries) => {
+                let out = entries
+                    .iter()
+                    .map(|(k, val)| (k.clone(), val.visit(v.clone())))
+                    .collect();
+                v.visited_map(out)
+            }
+            Value::Struct { r#type, fields } => {
+                let out = fields
+                    .iter()
+                    .map(|(k, val)| (k.clone(), val.visit(v.clone())))
+                    .collect();
+                v.visited_struct(r#type.clone(), out)
+            }
+            Value::Shared(idx, inner) => {
+                let out = inner.visit(v.clone());
+                v.visited_shared(*idx, out)
+            }
+            Value::Ref(idx) => v.visited_ref(*idx),
+        }
+    }
+
+    fn visit_into<V: IntoVisitor<Leaf>>(self, _v: V) -> V::Output {
+        todo!("owned traversal isn't needed by to_value/from_value yet")
+    }
+}
+
+// Visitor that materializes a value into the `Value` tree by running the
+// normal `Visitor` walk -- this is what backs `to_value`.
+#[derive(Clone)]
+pub struct ValueVisitor<'a, 'ctx, Leaf> {
+    _marker: std::marker::PhantomData<Value<'a, 'ctx, Leaf>>,
+}
+
+impl<'a, 'ctx, Leaf> ValueVisitor<'a, 'ctx, Leaf> {
+    pub fn new() -> Self {
+        ValueVisitor {
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, 'ctx, Leaf> Default for ValueVisitor<'a, 'ctx, Leaf> {
+    fn default() -> Self {
+        ValueVisitor::new()
+    }
 }
 
-trait GraphDeserialize<Leaf> {
-    fn
-}
-
-// pub trait EnumModel<'ctx> {
-//     fn num_variants(&self) -> usize;
-//     fn variant_name(&self, i: usize) -> Option<&str>;
-//     fn visit_variant<V: ModelVisitor<'ctx>>(&self, i: usize, v: V) -> V::Output;
-// }
-
-// // Serialization types
-// pub trait ContainerSerialize<'ctx>: ContainerModel<'ctx> + Sized {
-//     fn serialize_children<S: GraphSerializer<'ctx>>(&mut self, s: &mut S) -> Result<(), S::Err>;
-//     fn serialize_children_into<S: GraphSerializer<'ctx>>(self, s: &mut S) -> Result<(), S::Err>;
-// }
-// pub trait VariantSerialize<'ctx>: EnumModel<'ctx> {
-//     fn variant_type(&self) -> usize;
-//     fn serialize_data<S: GraphSerializer<'ctx>>(&mut self, s: &mut S) -> Result<(), S::Err>;
-// }
-
-// pub trait GraphSerializer<'ctx> {
-//     type Output;
-//     type Err;
-
-//     fn serialize_prim<T: Into<Primitive>>(&mut self, prim: T) -> Result<(), Self::Err>;
-
-//     // Allows the serializer to assume ownership of the values
-//     fn serialize_string<T: Into<String>>(&mut self, t: T) -> Result<(), Self::Err>;
-//     fn serialize_bytes<T: Into<Vec<u8>>>(&mut self, t: T) -> Result<(), Self::Err>;
-//     fn serialize_array<T: Into<Array<'ctx>>>(&mut self, t: T) -> Result<(), Self::Err>;
-
-//     // Makes a copy of the values
-//     fn serialize_str<T: AsRef<str>>(&mut self, t: T) -> Result<(), Self::Err>;
-//     fn serialize_bytes_slice<T: AsRef<[u8]>>(&mut self, t: &T) -> Result<(), Self::Err>;
-//     fn serialize_array_view<T>(&mut self, t: T) -> Result<(), Self::Err>
-//     where
-//         for<'a> T: Into<ArrayView<'a, 'ctx>>;
-
-//     // Serialize a generic map (aka dictionary)
-//     fn serialize_map(&mut self, fields: impl ContainerSerialize<'ctx>) -> Result<(), Self::Err>;
-//     fn serialize_struct(
-//         &mut self,
-//         typ: &TypeInfo,
-//         fields: impl ContainerSerialize<'ctx>,
-//     ) -> Result<(), Self::Err>;
-
-//     fn serialize_list(&mut self, entries: impl ContainerSerialize<'ctx>) -> Result<(), Self::Err>;
-//     fn serialize_tuple(&mut self, entries: impl ContainerSerialize<'ctx>) -> Result<(), Self::Err>;
-//     fn serialize_named_tuple(
-//         &mut self,
-//         typ: &TypeInfo,
-//         entries: impl ContainerSerialize<'ctx>,
-//     ) -> Result<(), Self::Err>;
-
-//     fn serialize_variant(&mut self, variant: impl VariantSerialize<'ctx>) -> Result<(), Self::Err>;
-
-//     // Small wrappers
-//     fn serialize_newtype_struct(
-//         &mut self,
-//         typ: &TypeInfo,
-//         payload: impl GraphSerializable<'ctx>,
-//     ) -> Result<(), Self::Err>;
-//     fn serialize_newtype_variant(
-//         &mut self,
-//         typ: &TypeInfo,
-//         variant_name: &str,
-//         payload: impl GraphSerializable<'ctx>,
-//     ) -> Result<(), Self::Err>;
-
-//     fn serialize_ref<T>(&mut self, r: impl Ref<T>) -> Result<(), Self::Err>
-//     where
-//         T: GraphSerializable<'ctx>;
-
-//     fn finish(self) -> Result<Self::Output, Self::Err>;
-// }
-
-// pub trait GraphSerializable<'ctx> {
-//     fn serialize<S>(&self, s: S)
-//     where
-//         S: GraphSerializer<'ctx>;
-
-//     fn serialize_into<S>(self, s: S)
-//     where
-//         S: GraphSerializer<'ctx>;
-// }
-
-// // Deserialization!
-
-// pub trait ContainerDeserialize<'buf, 'ctx> {
-//     fn num_children_hint(&self) -> Option<usize>;
-//     fn next_child(&mut self) -> Option<impl GraphDeserializer>;
-//     // Returns key, value pair
-//     fn next_entry(&mut self) -> Option<(Cow<'buf, str>, impl GraphDeserializer)>;
-// }
-
-// pub trait GraphDeserializer<'buf, 'ctx> {
-//     type Err;
-//     // Primitive deserialization
-//     fn deserialize_prim(&mut self, prim_type: PrimitiveType) -> Result<Primitive, Self::Err>;
-//     // A helper function to deserialize a primitive
-//     fn deserialize_prim_as<T: AsPrimitiveType + TryFrom<Primitive>>(
-//         &mut self,
-//     ) -> Result<T, Self::Err> {
-//         let ty: PrimitiveType = T::as_primitive_type();
-//         let r: Primitive = self.deserialize_prim(ty)?;
-//         match r.try_into() {
-//             Ok(s) => Ok(s),
-//             Err(_) => panic!("AsPrimitiveType failed TryFrom for {ty:?}"),
-//         }
-//     }
-
-//     fn deserialize_string(&mut self) -> Result<Cow<'buf, str>, Self::Err>;
-//     fn deserialize_bytes(&mut self) -> Result<Cow<'buf, [u8]>, Self::Err>;
-//     fn deserialize_array(&mut self) -> Result<CowArray<'buf, 'ctx>, Self::Err>;
-
-//     fn deserialize_map(&mut self) -> () {
-//         panic!()
-//     }
-
-//     fn deserialize_ref<R, T>(&mut self) -> Result<T, Self::Err>
-//     where
-//         R: Ref<T>,
-//         T: GraphDeserialize<'buf, 'ctx>;
-// }
+impl<'a, 'ctx, Leaf: Clone> Visitor<Leaf> for ValueVisitor<'a, 'ctx, Leaf> {
+    type Output = Value<'a, 'ctx, Leaf>;
+
+    fn visited_primitive(self, prim: Primitive) -> Self::Output {
+        Value::Primitive(prim)
+    }
+    fn visited_leaf(self, leaf: Leaf) -> Self::Output {
+        Value::Leaf(leaf)
+    }
+    fn visited_seq(self, elems: Vec<Self::Output>) -> Self::Output {
+        Value::List(elems)
+    }
+    fn visited_tuple(self, elems: Vec<Self::Output>) -> Self::Output {
+        Value::Tuple(elems)
+    }
+    fn visited_map(self, entries: Vec<(String, Self::Output)>) -> Self::Output {
+        Value::Map(entries)
+    }
+    fn visited_struct(self, ty: TypeInfo<'_>, fields: Vec<(String, Self::Output)>) -> Self::Output {
+        Value::Struct {
+            r#type: TypeInfo::owned(ty.name().to_string()),
+            fields,
+        }
+    }
+    fn visited_array(self, array: CowArray<'_, '_>) -> Self::Output {
+        // `array::ArrayView::to_owned` is itself unimplemented upstream
+        // (the array subsystem has no real backing storage yet), so
+        // there's no data here to rehost into `Value`'s own lifetimes.
+        let _ = array;
+        todo!("Value::Array round-tripping is blocked on array::ArrayView::to_owned")
+    }
+    fn visited_shared(self, idx: usize, inner: Self::Output) -> Self::Output {
+        Value::Shared(idx, Box::new(inner))
+    }
+    fn visited_ref(self, idx: usize) -> Self::Output {
+        Value::Ref(idx)
+    }
+}
+
+// Builds a `Value` out of any serializable model, by running the
+// existing `Visitor` walk into a `ValueVisitor` rather than a concrete
+// output type.
+pub fn to_value<'a, 'ctx, Leaf: Clone>(model: &impl GraphSerialize<Leaf>) -> Value<'a, 'ctx, Leaf> {
+    model.visit(ValueVisitor::new())
+}
+
+// Drives a `Visitor` from an already-built `Value` tree -- the inverse of
+// `to_value`. Because `Value` is already fully self-describing, no
+// separate `GraphModel` schema is needed to replay it.
+pub fn from_value<Leaf: Clone, V: Visitor<Leaf>>(value: Value<'_, '_, Leaf>, v: V) -> V::Output {
+    value.visit(v)
+}
+
+// A buffered, tag-dispatchable representation of one decoded value --
+// mirrors serde's `Content`. Lets a deserializer peek at a value's shape
+// (e.g. find a tag embedded in a map) before committing to which variant
+// to finish decoding it into.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content<Leaf> {
+    Primitive(Primitive),
+    Leaf(Leaf),
+    Seq(Vec<Content<Leaf>>),
+    Map(Vec<(String, Content<Leaf>)>),
+    // A back-reference to an earlier-buffered shared value, by index.
+    // Tag-dispatch never needs to resolve it (the `Shared` wrapper itself
+    // is transparent -- see `ContentVisitor::visited_shared`), so this is
+    // the only ref-related shape `Content` needs to represent.
+    Ref(usize),
+}
+
+// Visitor that fully materializes one value into a `Content` tree instead
+// of driving a concrete type, so the caller can inspect it before
+// deciding how to finish decoding.
+#[derive(Clone)]
+pub struct ContentVisitor;
+
+impl<Leaf: Clone> Visitor<Leaf> for ContentVisitor {
+    type Output = Content<Leaf>;
+
+    fn visited_primitive(self, prim: Primitive) -> Self::Output {
+        Content::Primitive(prim)
+    }
+    fn visited_leaf(self, leaf: Leaf) -> Self::Output {
+        Content::Leaf(leaf)
+    }
+    fn visited_seq(self, elems: Vec<Self::Output>) -> Self::Output {
+        Content::Seq(elems)
+    }
+    fn visited_tuple(self, elems: Vec<Self::Output>) -> Self::Output {
+        // Content doesn't distinguish a tuple from a seq -- both are
+        // just "an ordered run of values" for tag-dispatch purposes.
+        Content::Seq(elems)
+    }
+    fn visited_map(self, entries: Vec<(String, Self::Output)>) -> Self::Output {
+        Content::Map(entries)
+    }
+    fn visited_struct(self, _ty: TypeInfo<'_>, fields: Vec<(String, Self::Output)>) -> Self::Output {
+        // Likewise, a struct is just a map once the type name is
+        // dropped; tag dispatch only needs to read its fields.
+        Content::Map(fields)
+    }
+    fn visited_array(self, _array: CowArray<'_, '_>) -> Self::Output {
+        // No element data is accessible yet (see `ValueVisitor`'s
+        // `visited_array`); an empty seq at least keeps tag dispatch over
+        // the rest of the document usable instead of panicking.
+        Content::Seq(Vec::new())
+    }
+    fn visited_shared(self, _idx: usize, inner: Self::Output) -> Self::Output {
+        // Tag dispatch only cares about a value's shape, not its
+        // identity, so sharing is transparent here: the wrapper is
+        // dropped and buffering proceeds as if it were the bare value.
+        inner
+    }
+    fn visited_ref(self, idx: usize) -> Self::Output {
+        Content::Ref(idx)
+    }
+}
+
+// Deserializer that replays an already-buffered `Content` tree through
+// any `Visitor`, so a value can be buffered once (e.g. to peek at a tag)
+// and then decoded for real without re-reading the underlying source.
+pub struct ContentDeserializer<Leaf> {
+    content: Content<Leaf>,
+}
+
+impl<Leaf: Clone> ContentDeserializer<Leaf> {
+    pub fn new(content: Content<Leaf>) -> Self {
+        ContentDeserializer { content }
+    }
+
+    pub fn content(&self) -> &Content<Leaf> {
+        &self.content
+    }
+
+    fn replay<V: Visitor<Leaf>>(content: &Content<Leaf>, v: V) -> V::Output {
+        match content {
+            Content::Primitive(p) => v.visited_primitive(*p),
+            Content::Leaf(l) => v.visited_leaf(l.clone()),
+            Content::Seq(items) => {
+                let out = items.iter().map(|item| Self::replay(item, v.clone())).collect();
+                v.visited_seq(out)
+            }
+            Content::Map(entries) => {
+                let out = entries
+                    .iter()
+                    .map(|(k, val)| (k.clone(), Self::replay(val, v.clone())))
+                    .collect();
+                v.visited_map(out)
+            }
+            Content::Ref(idx) => v.visited_ref(*idx),
+        }
+    }
+
+    // Externally tagged dispatch: `{ "<variant>": <payload> }` -- a
+    // single-entry map whose key names the variant.
+    pub fn external_tag(&self) -> Option<(&str, &Content<Leaf>)> {
+        match &self.content {
+            Content::Map(entries) if entries.len() == 1 => {
+                Some((entries[0].0.as_str(), &entries[0].1))
+            }
+            _ => None,
+        }
+    }
+
+    // Internally tagged dispatch: the variant name lives alongside its
+    // own fields in the same map (e.g. `{ "type": "<variant>", ... }`).
+    // The tag is read out, but the whole map (tag field included) is
+    // handed back so the caller can still decode its other fields.
+    pub fn internal_tag(&self, tag_field: &str) -> Option<(&str, &Content<Leaf>)>
+    where
+        Leaf: AsRef<str>,
+    {
+        let Content::Map(entries) = &self.content else {
+            return None;
+        };
+        let tag = entries
+            .iter()
+            .find(|(k, _)| k == tag_field)
+            .and_then(|(_, v)| match v {
+                Content::Leaf(l) => Some(l.as_ref()),
+                _ => None,
+            })?;
+        Some((tag, &self.content))
+    }
+
+    // Adjacently tagged dispatch: a map with a `tag_field` naming the
+    // variant and a separate `content_field` holding its payload.
+    pub fn adjacent_tag<'s>(
+        &'s self,
+        tag_field: &str,
+        content_field: &str,
+    ) -> Option<(&'s str, &'s Content<Leaf>)>
+    where
+        Leaf: AsRef<str>,
+    {
+        let Content::Map(entries) = &self.content else {
+            return None;
+        };
+        let tag = entries
+            .iter()
+            .find(|(k, _)| k == tag_field)
+            .and_then(|(_, v)| match v {
+                Content::Leaf(l) => Some(l.as_ref()),
+                _ => None,
+            })?;
+        let payload = entries
+            .iter()
+            .find(|(k, _)| k == content_field)
+            .map(|(_, v)| v)?;
+        Some((tag, payload))
+    }
+
+    // Untagged dispatch: try each candidate visitor against the same
+    // buffered content in turn, short-circuiting on the first success
+    // (the visitor itself reports success/failure through its `Output`).
+    // Because `self.content` was already fully materialized by
+    // `ContentVisitor`, every attempt replays the same in-memory tree --
+    // no re-reading (and so no double consumption) of the underlying
+    // source ever happens between attempts.
+    pub fn try_untagged<T, E, V>(&self, candidates: impl IntoIterator<Item = V>) -> Option<T>
+    where
+        V: Visitor<Leaf, Output = Result<T, E>>,
+    {
+        candidates
+            .into_iter()
+            .find_map(|v| Self::replay(&self.content, v).ok())
+    }
+}
+
+impl<Leaf: Clone> GraphDeserializer<Leaf> for ContentDeserializer<Leaf> {
+    type Err = std::convert::Infallible;
+
+    fn deserialize<V: Visitor<Leaf>>(
+        &mut self,
+        _t: &impl GraphModel<Leaf>,
+        v: V,
+    ) -> Result<V::Output, Self::Err> {
+        Ok(Self::replay(&self.content, v))
+    }
+
+    fn deserialize_index(&mut self) -> Result<usize, Self::Err> {
+        unreachable!("Content has no notion of indirect references")
+    }
+}
+
+// Indirect-object table for the serializing side: assigns a small integer
+// index to each `Ref::id()` the first time it is seen, modeled on PDF's
+// indirect reference/resolve mechanism. `reserve` must be called *before*
+// recursing into a ref's children -- that way a cycle back to an
+// already-reserved id is reported as "seen" and the caller can emit a
+// reference instead of recursing forever, and a shared subgraph is only
+// written out once no matter how many times it's encountered.
+#[derive(Default)]
+pub struct RefTable {
+    indices: HashMap<Uuid, usize>,
+    next: usize,
+}
+
+impl RefTable {
+    pub fn new() -> Self {
+        RefTable::default()
+    }
+
+    // Returns the index assigned to `id` and whether this is the first
+    // time it has been reserved. Callers should only recurse into the
+    // referenced object's children when `true` is returned.
+    pub fn reserve(&mut self, id: Uuid) -> (usize, bool) {
+        if let Some(&idx) = self.indices.get(&id) {
+            return (idx, false);
+        }
+        let idx = self.next;
+        self.next += 1;
+        self.indices.insert(id, idx);
+        (idx, true)
+    }
+}
+
+// The deserializing-side counterpart: resolves an index back to the
+// object that was decoded for it, so a later reference to an
+// already-decoded index is satisfied without re-reading its encoding.
+pub struct RefResolver<T> {
+    objects: Vec<Option<T>>,
+}
+
+// Written by hand rather than `#[derive(Default)]`, which would add a
+// spurious `T: Default` bound -- an empty `Vec` needs no such bound.
+impl<T> Default for RefResolver<T> {
+    fn default() -> Self {
+        RefResolver { objects: Vec::new() }
+    }
+}
+
+impl<T> RefResolver<T> {
+    pub fn new() -> Self {
+        RefResolver::default()
+    }
+
+    // Registers the object decoded for `idx`, growing the table as needed
+    // (objects may be resolved out of order, e.g. a forward reference).
+    pub fn insert(&mut self, idx: usize, value: T) {
+        if idx >= self.objects.len() {
+            self.objects.resize_with(idx + 1, || None);
+        }
+        self.objects[idx] = Some(value);
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        self.objects.get(idx).and_then(Option::as_ref)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_value() -> Value<'static, 'static, String> {
+        Value::Struct {
+            r#type: TypeInfo::owned("Point"),
+            fields: vec![
+                ("x".to_string(), Value::Primitive(Primitive::I32(1))),
+                ("y".to_string(), Value::Primitive(Primitive::I32(2))),
+                (
+                    "tags".to_string(),
+                    Value::List(vec![
+                        Value::Leaf("a".to_string()),
+                        Value::Leaf("b".to_string()),
+                    ]),
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn to_value_from_value_round_trip() {
+        let value = sample_value();
+        let rebuilt: Value<'static, 'static, String> =
+            from_value(value.clone(), ValueVisitor::new());
+        assert_eq!(value, rebuilt);
+    }
+
+    #[test]
+    fn to_value_reads_through_the_graph_serialize_impl() {
+        let value = sample_value();
+        let via_to_value: Value<'static, 'static, String> = to_value(&value);
+        assert_eq!(value, via_to_value);
+    }
+
+    #[test]
+    fn ref_table_assigns_each_id_once_and_reports_first_sight() {
+        let id = Uuid::new_v4();
+        let mut table = RefTable::new();
+        let (first_idx, first) = table.reserve(id);
+        let (second_idx, second) = table.reserve(id);
+        assert!(first);
+        assert!(!second);
+        assert_eq!(first_idx, second_idx);
+    }
+
+    #[test]
+    fn ref_resolver_round_trips_an_inserted_value() {
+        let mut resolver = RefResolver::new();
+        resolver.insert(3, "shared".to_string());
+        assert_eq!(resolver.get(3), Some(&"shared".to_string()));
+        assert_eq!(resolver.get(0), None);
+    }
+
+    #[test]
+    fn content_external_tag_reads_the_single_variant_key() {
+        let content: Content<String> = Content::Map(vec![(
+            "Dog".to_string(),
+            Content::Map(vec![("name".to_string(), Content::Leaf("Rex".to_string()))]),
+        )]);
+        let deser = ContentDeserializer::new(content);
+        let (tag, payload) = deser.external_tag().expect("externally tagged");
+        assert_eq!(tag, "Dog");
+        assert_eq!(
+            payload,
+            &Content::Map(vec![("name".to_string(), Content::Leaf("Rex".to_string()))])
+        );
+    }
+
+    #[test]
+    fn content_adjacent_tag_reads_tag_and_payload_fields() {
+        let content: Content<String> = Content::Map(vec![
+            ("t".to_string(), Content::Leaf("Cat".to_string())),
+            (
+                "c".to_string(),
+                Content::Map(vec![("lives".to_string(), Content::Primitive(Primitive::I32(9)))]),
+            ),
+        ]);
+        let deser = ContentDeserializer::new(content);
+        let (tag, payload) = deser.adjacent_tag("t", "c").expect("adjacently tagged");
+        assert_eq!(tag, "Cat");
+        assert_eq!(
+            payload,
+            &Content::Map(vec![("lives".to_string(), Content::Primitive(Primitive::I32(9)))])
+        );
+    }
+
+    #[test]
+    fn content_internal_tag_keeps_the_tag_field_in_the_returned_map() {
+        let content: Content<String> = Content::Map(vec![
+            ("type".to_string(), Content::Leaf("Square".to_string())),
+            ("side".to_string(), Content::Primitive(Primitive::I32(4))),
+        ]);
+        let deser = ContentDeserializer::new(content);
+        let (tag, payload) = deser.internal_tag("type").expect("internally tagged");
+        assert_eq!(tag, "Square");
+        assert_eq!(payload, deser.content());
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Shape {
+        Square(i32),
+        Circle(i32),
+    }
+
+    // Tries to read the buffered content as the given `Shape` variant,
+    // matching it against a single leading primitive.
+    #[derive(Clone)]
+    struct ExpectShape(fn(i32) -> Shape);
+    impl Visitor<String> for ExpectShape {
+        type Output = Result<Shape, ()>;
+        fn visited_primitive(self, prim: Primitive) -> Self::Output {
+            match prim {
+                Primitive::I32(v) => Ok((self.0)(v)),
+                _ => Err(()),
+            }
+        }
+        fn visited_leaf(self, _leaf: String) -> Self::Output {
+            Err(())
+        }
+        fn visited_seq(self, _elems: Vec<Self::Output>) -> Self::Output {
+            Err(())
+        }
+        fn visited_tuple(self, _elems: Vec<Self::Output>) -> Self::Output {
+            Err(())
+        }
+        fn visited_map(self, _entries: Vec<(String, Self::Output)>) -> Self::Output {
+            Err(())
+        }
+        fn visited_struct(
+            self,
+            _ty: TypeInfo<'_>,
+            _fields: Vec<(String, Self::Output)>,
+        ) -> Self::Output {
+            Err(())
+        }
+        fn visited_array(self, _array: CowArray<'_, '_>) -> Self::Output {
+            Err(())
+        }
+        fn visited_shared(self, _idx: usize, _inner: Self::Output) -> Self::Output {
+            Err(())
+        }
+        fn visited_ref(self, _idx: usize) -> Self::Output {
+            Err(())
+        }
+    }
+
+    #[test]
+    fn content_untagged_tries_each_candidate_against_the_same_buffer() {
+        let content: Content<String> = Content::Primitive(Primitive::I32(4));
+        let deser = ContentDeserializer::new(content);
+
+        // Neither candidate can tell from the primitive alone which
+        // variant it should produce, so the first candidate in the list
+        // always claims success and the second is never run -- what this
+        // test actually proves is that each candidate, run in its own
+        // call, sees the exact same buffered content (the first call
+        // doesn't leave a half-consumed source behind for the second).
+        let result = deser.try_untagged(vec![ExpectShape(Shape::Square), ExpectShape(Shape::Circle)]);
+        assert_eq!(result, Some(Shape::Square(4)));
+
+        let result = deser.try_untagged(vec![ExpectShape(Shape::Circle), ExpectShape(Shape::Square)]);
+        assert_eq!(result, Some(Shape::Circle(4)));
+    }
+}
@@ -1,4 +1,5 @@
 pub mod array;
+pub mod format;
 pub mod graph;
 pub mod io;
 pub mod types;
@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PrimitiveType {
     Bool,
     I8,
@@ -25,7 +25,7 @@ impl AsPrimitiveType for u8 {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Primitive {
     Bool(bool),
     I8(i8),
@@ -40,6 +40,48 @@ pub enum Primitive {
     F64(f64),
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct TypeInfo<'a> {
     name: Cow<'a, str>,
 }
+
+impl<'a> TypeInfo<'a> {
+    pub fn borrowed(name: &'a str) -> Self {
+        TypeInfo {
+            name: Cow::Borrowed(name),
+        }
+    }
+
+    // Builds a `TypeInfo` that owns its name, so it isn't tied to any
+    // particular input lifetime (e.g. when re-hosting a name read off of
+    // one buffer into a `Value` meant to outlive it).
+    pub fn owned(name: impl Into<String>) -> TypeInfo<'static> {
+        TypeInfo {
+            name: Cow::Owned(name.into()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Primitive {
+    // The fixed, known-ahead-of-time width of this primitive's value.
+    pub fn estimate_size(&self) -> crate::graph::SizeHint {
+        let bytes = match self {
+            Primitive::Bool(_) => std::mem::size_of::<bool>(),
+            Primitive::I8(_) => std::mem::size_of::<i8>(),
+            Primitive::I16(_) => std::mem::size_of::<i16>(),
+            Primitive::I32(_) => std::mem::size_of::<i32>(),
+            Primitive::I64(_) => std::mem::size_of::<i64>(),
+            Primitive::U8(_) => std::mem::size_of::<u8>(),
+            Primitive::U16(_) => std::mem::size_of::<u16>(),
+            Primitive::U32(_) => std::mem::size_of::<u32>(),
+            Primitive::U64(_) => std::mem::size_of::<u64>(),
+            Primitive::F32(_) => std::mem::size_of::<f32>(),
+            Primitive::F64(_) => std::mem::size_of::<f64>(),
+        };
+        crate::graph::SizeHint::fixed(bytes)
+    }
+}